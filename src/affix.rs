@@ -0,0 +1,182 @@
+use std::f32::consts::TAU;
+
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use rand::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    archetype::EnemyArchetypes,
+    behaviour::DeathOnZeroHealth,
+    enemy::{Enemy, Spawner},
+    pattern::{Pattern, Shape},
+    Clearable, Cycle, Handles, Vel,
+};
+
+/// Modifier making an enemy an elite
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Affix {
+    /// Twice the health
+    Armored,
+    /// Moves faster
+    Hasty,
+    /// Splits into two on death
+    Splitting,
+    /// Blocks bullets hitting it from the front
+    Shielded,
+    /// Bursts into a ring of bullets on death
+    Exploding,
+}
+
+impl Affix {
+    /// Tint of the enemy's occluder sprite
+    pub fn color(self) -> Color {
+        match self {
+            Affix::Armored => Color::srgba(0.6, 0.7, 1., 0.6),
+            Affix::Hasty => Color::srgba(1., 0.9, 0.3, 0.5),
+            Affix::Splitting => Color::srgba(0.4, 1., 0.5, 0.5),
+            Affix::Shielded => Color::srgba(0.5, 0.95, 1., 0.6),
+            Affix::Exploding => Color::srgba(1., 0.45, 0.2, 0.6),
+        }
+    }
+}
+
+/// How likely enemies are to be elites in a cycle, and which affixes they get
+#[derive(Deserialize, Clone)]
+pub struct EliteTable {
+    pub chance: f64,
+    /// An elite gets between one and this many different affixes
+    pub max_affixes: usize,
+    pub weights: Vec<(Affix, f32)>,
+}
+
+impl EliteTable {
+    pub fn roll(&self) -> Vec<Affix> {
+        let mut rng = thread_rng();
+        if (self.max_affixes == 0) | !rng.gen_bool(self.chance) {
+            return Vec::new();
+        }
+        let count = rng.gen_range(1..=self.max_affixes);
+        self.weights
+            .choose_multiple_weighted(&mut rng, count, |(_, weight)| *weight)
+            .map_or_else(
+                |_| Vec::new(),
+                |chosen| chosen.map(|(affix, _)| *affix).collect(),
+            )
+    }
+}
+
+/// Affixes for the enemy a spawner summons
+#[derive(Component, Default)]
+pub struct Affixes(pub Vec<Affix>);
+
+impl Affixes {
+    pub fn roll(archetypes: &EnemyArchetypes, cycle: &Cycle) -> Self {
+        Self(
+            archetypes
+                .elite_table(cycle.cycle)
+                .map_or_else(Vec::new, EliteTable::roll),
+        )
+    }
+
+    pub fn health_factor(&self) -> f32 {
+        if self.0.contains(&Affix::Armored) {
+            2.
+        } else {
+            1.
+        }
+    }
+
+    pub fn tint(&self) -> Color {
+        self.0
+            .first()
+            .map_or(Color::srgba(1., 1., 1., 0.), |affix| affix.color())
+    }
+
+    /// Adds the components for affixes with ongoing effects
+    pub fn insert(&self, entity: &mut EntityCommands, kind: &str) {
+        for affix in &self.0 {
+            match affix {
+                Affix::Armored => {}
+                Affix::Hasty => {
+                    entity.insert(Haste(1.6));
+                }
+                Affix::Splitting => {
+                    entity.insert(Splitting {
+                        kind: kind.to_owned(),
+                    });
+                }
+                Affix::Shielded => {
+                    entity.insert(Shielded { facing: Vec2::Y });
+                }
+                Affix::Exploding => {
+                    entity.insert(Exploding);
+                }
+            }
+        }
+    }
+}
+
+/// Multiplies how far an enemy moves
+#[derive(Component)]
+pub struct Haste(pub f32);
+
+#[derive(Component)]
+pub struct Splitting {
+    kind: String,
+}
+
+#[derive(Component)]
+pub struct Shielded {
+    /// Direction the shield is held in, the last direction the enemy moved in
+    pub facing: Vec2,
+}
+
+#[derive(Component)]
+pub struct Exploding;
+
+pub fn turn_shields(mut query: Query<(&Vel, &mut Shielded)>) {
+    for (vel, mut shield) in &mut query {
+        if vel.length() > 1. {
+            shield.facing = vel.normalize();
+        }
+    }
+}
+
+pub fn split_on_death(
+    mut commands: Commands,
+    query: Query<(&Transform, &Enemy, &Splitting), With<DeathOnZeroHealth>>,
+) {
+    for (trans, enemy, splitting) in &query {
+        if enemy.health > 0. {
+            continue;
+        }
+        let angle = thread_rng().gen_range(0.0..TAU);
+        for i in 0..2 {
+            let offset = Vec2::from_angle(angle + i as f32 * TAU / 2.) * enemy.size;
+            commands.spawn((
+                Spawner::create(
+                    trans.translation.xy() + offset,
+                    splitting.kind.clone(),
+                    -0.5,
+                ),
+                Clearable,
+            ));
+        }
+    }
+}
+
+pub fn explode_on_death(
+    mut commands: Commands,
+    query: Query<(&Transform, &Enemy), (With<Exploding>, With<DeathOnZeroHealth>)>,
+    handles: Res<Handles>,
+) {
+    for (trans, enemy) in &query {
+        if enemy.health > 0. {
+            continue;
+        }
+        let mut pattern = Pattern::new(Shape::Ring { count: 10, gap: 0 }, 50.);
+        pattern.offset = enemy.size;
+        let aim = thread_rng().gen_range(0.0..TAU);
+        pattern.fire(&mut commands, &handles, trans.translation.xy(), aim);
+    }
+}
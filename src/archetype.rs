@@ -0,0 +1,278 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    ecs::system::EntityCommands,
+    prelude::*,
+    reflect::TypePath,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::{
+    affix::EliteTable,
+    aseprite::AnimationData,
+    behaviour::{Chase, ContactDamage, DeathOnZeroHealth, Separation, Summoner, Wander},
+    boss::Boss,
+    pattern::{Emitter, Pattern},
+    pickup::DropChance,
+    pit::Grounded,
+    telegraph::Telegraph,
+};
+
+/// A part an enemy is assembled from, see the components in `behaviour`
+#[derive(Deserialize, Clone)]
+pub enum Behaviour {
+    Wander {
+        propulsion: f32,
+        burst: f32,
+        period: f32,
+        #[serde(default)]
+        chase: f32,
+    },
+    Chase {
+        speed: f32,
+    },
+    Separation,
+    ContactDamage,
+    Emitter {
+        pattern: Pattern,
+        interval: f32,
+        first: f32,
+        #[serde(default = "certain")]
+        chance: f64,
+        #[serde(default)]
+        needs_sight: bool,
+    },
+    Summoner {
+        enemy: String,
+        first: f32,
+        interval: f32,
+    },
+    Telegraph(Telegraph),
+    Grounded,
+    DeathOnZeroHealth,
+    Boss {
+        /// Enemy called for help in the later phases
+        summon: String,
+    },
+}
+
+#[derive(Deserialize)]
+struct ArchetypeDef {
+    animation: String,
+    occluded: String,
+    #[serde(default)]
+    color: Option<[f32; 3]>,
+    #[serde(default = "default_scale")]
+    scale: f32,
+    health: f32,
+    size: f32,
+    #[serde(default)]
+    knockback_resistance: f32,
+    behaviours: Vec<Behaviour>,
+    #[serde(default)]
+    drops: Vec<DropChance>,
+}
+
+fn default_scale() -> f32 {
+    1.
+}
+
+fn certain() -> f64 {
+    1.
+}
+
+#[derive(Deserialize)]
+struct ArchetypesDef {
+    enemies: HashMap<String, ArchetypeDef>,
+    spawn_tables: Vec<Vec<(String, f32)>>,
+    #[serde(default)]
+    elites: Vec<EliteTable>,
+}
+
+pub struct EnemyArchetype {
+    pub animation: Handle<AnimationData>,
+    /// Shown through walls and while being summoned
+    pub occluded: Handle<AnimationData>,
+    pub color: Color,
+    pub scale: f32,
+    pub health: f32,
+    pub size: f32,
+    /// 0 is pushed back fully by hits, 1 not at all
+    pub knockback_resistance: f32,
+    pub behaviours: Vec<Behaviour>,
+    pub drops: Vec<DropChance>,
+}
+
+impl EnemyArchetype {
+    pub fn insert_behaviours(&self, entity: &mut EntityCommands) {
+        for behaviour in &self.behaviours {
+            match behaviour.clone() {
+                Behaviour::Wander {
+                    propulsion,
+                    burst,
+                    period,
+                    chase,
+                } => entity.insert(Wander::new(propulsion, burst, period).chasing(chase)),
+                Behaviour::Chase { speed } => entity.insert(Chase { speed }),
+                Behaviour::Separation => entity.insert(Separation),
+                Behaviour::ContactDamage => entity.insert(ContactDamage),
+                Behaviour::Emitter {
+                    pattern,
+                    interval,
+                    first,
+                    chance,
+                    needs_sight,
+                } => entity.insert(
+                    Emitter::new(pattern, interval)
+                        .first(first)
+                        .chance(chance)
+                        .needs_sight(needs_sight),
+                ),
+                Behaviour::Summoner {
+                    enemy,
+                    first,
+                    interval,
+                } => entity.insert(Summoner::new(enemy, first, interval)),
+                Behaviour::Telegraph(telegraph) => entity.insert(telegraph),
+                Behaviour::Grounded => entity.insert(Grounded),
+                Behaviour::DeathOnZeroHealth => entity.insert(DeathOnZeroHealth),
+                Behaviour::Boss { summon } => entity.insert(Boss::new(summon)),
+            };
+        }
+    }
+}
+
+/// All enemy types by name, and which of them appear in randomly filled rooms
+#[derive(Asset, TypePath)]
+pub struct EnemyArchetypes {
+    pub enemies: HashMap<String, EnemyArchetype>,
+    /// Names with weights, by cycle. The last table is used for all later cycles.
+    pub spawn_tables: Vec<Vec<(String, f32)>>,
+    /// Affixes rolled for enemies in each cycle, the last one again used for later cycles
+    pub elites: Vec<EliteTable>,
+}
+
+impl EnemyArchetypes {
+    pub fn get(&self, name: &str) -> Option<&EnemyArchetype> {
+        let archetype = self.enemies.get(name);
+        if archetype.is_none() {
+            warn!("Unknown enemy {name}");
+        }
+        archetype
+    }
+
+    pub fn spawn_table(&self, cycle: i32) -> &[(String, f32)] {
+        let index = (cycle.max(0) as usize).min(self.spawn_tables.len() - 1);
+        &self.spawn_tables[index]
+    }
+
+    pub fn elite_table(&self, cycle: i32) -> Option<&EliteTable> {
+        let index = (cycle.max(0) as usize).min(self.elites.len().checked_sub(1)?);
+        Some(&self.elites[index])
+    }
+}
+
+pub struct EnemyArchetypesLoader;
+
+impl AssetLoader for EnemyArchetypesLoader {
+    type Asset = EnemyArchetypes;
+    type Settings = ();
+    type Error = anyhow::Error;
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let def: ArchetypesDef = serde_json::from_slice(&bytes)?;
+
+        // Catch typos in names here rather than when the enemy is first spawned
+        let known = |name: &String| {
+            if def.enemies.contains_key(name) {
+                Ok(())
+            } else {
+                Err(anyhow::anyhow!("Unknown enemy {name}"))
+            }
+        };
+        // Random rolls panic on bad chances and weights, so reject them while loading
+        let chance = |chance: f64| {
+            if (0. ..=1.).contains(&chance) {
+                Ok(())
+            } else {
+                Err(anyhow::anyhow!("Chance {chance} is not between 0 and 1"))
+            }
+        };
+        if def.spawn_tables.is_empty() {
+            anyhow::bail!("No spawn tables");
+        }
+        for table in &def.spawn_tables {
+            if table.is_empty() {
+                anyhow::bail!("Empty spawn table");
+            }
+            for (name, weight) in table {
+                known(name)?;
+                if !weight.is_finite() | (*weight <= 0.) {
+                    anyhow::bail!("Spawn weight {weight} of {name} is not positive");
+                }
+            }
+        }
+        for elites in &def.elites {
+            chance(elites.chance)?;
+            if elites
+                .weights
+                .iter()
+                .any(|(_, weight)| !weight.is_finite() | (*weight < 0.))
+            {
+                anyhow::bail!("Negative affix weight");
+            }
+        }
+        for (name, enemy) in &def.enemies {
+            for drop in &enemy.drops {
+                chance(drop.chance).map_err(|err| err.context(format!("Drop of {name}")))?;
+            }
+            for behaviour in &enemy.behaviours {
+                match behaviour {
+                    Behaviour::Summoner { enemy, .. } => known(enemy)?,
+                    Behaviour::Boss { summon } => known(summon)?,
+                    Behaviour::Emitter { chance: c, .. } => {
+                        chance(*c).map_err(|err| err.context(format!("Emitter of {name}")))?
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let enemies = def
+            .enemies
+            .into_iter()
+            .map(|(name, enemy)| {
+                let color = enemy
+                    .color
+                    .map_or(Color::WHITE, |[r, g, b]| Color::srgb(r, g, b));
+                let archetype = EnemyArchetype {
+                    animation: load_context.load(enemy.animation),
+                    occluded: load_context.load(enemy.occluded),
+                    color,
+                    scale: enemy.scale,
+                    health: enemy.health,
+                    size: enemy.size,
+                    knockback_resistance: enemy.knockback_resistance.clamp(0., 1.),
+                    behaviours: enemy.behaviours,
+                    drops: enemy.drops,
+                };
+                (name, archetype)
+            })
+            .collect();
+        Ok(EnemyArchetypes {
+            enemies,
+            spawn_tables: def.spawn_tables,
+            elites: def.elites,
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["enemies.json"]
+    }
+}
@@ -0,0 +1,396 @@
+use bevy::{math::vec3, prelude::*};
+use rand::prelude::*;
+
+use crate::{
+    affix::Haste,
+    camera::Shake,
+    collision::grid_collision,
+    enemy::{Enemy, Spawner},
+    hazards::{CONVEYOR_SPEED, GOO_SLOWDOWN},
+    level::{Tile, Tiles},
+    particle::{Burst, EmitParticles},
+    pathfinding::FlowField,
+    pickup::{spawn_drops, Drops},
+    pit::{Grounded, PitFalling},
+    player::{HurtPlayer, PlayerEntity, PLAYER_SIZE},
+    spatial::EnemyGrid,
+    Clearable, Handles, Layer, RoomState, Vel,
+};
+
+/// Floats off in a new direction every `period` seconds, propelling itself for the first
+/// `burst` seconds of it
+#[derive(Component)]
+pub struct Wander {
+    pub propulsion: f32,
+    pub burst: f32,
+    pub period: f32,
+    /// How strongly new directions lean towards the player
+    pub chase: f32,
+    timer: f32,
+}
+
+impl Wander {
+    pub fn new(propulsion: f32, burst: f32, period: f32) -> Self {
+        Self {
+            propulsion,
+            burst,
+            period,
+            chase: 0.,
+            timer: 0.,
+        }
+    }
+
+    pub fn chasing(self, chase: f32) -> Self {
+        Self { chase, ..self }
+    }
+}
+
+/// Steadily walks towards the player, around walls and pits if needed
+#[derive(Component)]
+pub struct Chase {
+    pub speed: f32,
+}
+
+/// Pushes away from other enemies
+#[derive(Component)]
+pub struct Separation;
+
+/// Hurts the player on touch
+#[derive(Component)]
+pub struct ContactDamage;
+
+/// Summons more enemies while the room is being fought in
+#[derive(Component)]
+pub struct Summoner {
+    /// Name of the enemy archetype to summon
+    pub enemy: String,
+    pub interval: f32,
+    timer: f32,
+}
+
+impl Summoner {
+    pub fn new(enemy: String, first: f32, interval: f32) -> Self {
+        Self {
+            enemy,
+            interval,
+            timer: first,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct DeathOnZeroHealth;
+
+/// Counts down hit-stun, while the knockback from the hit slows down
+pub fn stagger(mut query: Query<(&mut Vel, &mut Enemy)>, time: Res<Time>) {
+    for (mut vel, mut enemy) in &mut query {
+        if enemy.stunned() {
+            enemy.stun -= time.delta_seconds();
+            vel.0 *= (1. - time.delta_seconds() * 8.).max(0.);
+        }
+    }
+}
+
+/// Direction to the player, following the flow field when they're out of sight
+fn towards_player(
+    tiles: &Tiles,
+    flow_field: &FlowField,
+    pos: Vec2,
+    player: Vec2,
+    flying: bool,
+) -> Vec2 {
+    let straight = (player - pos).normalize_or_zero();
+    if tiles.raycast(pos, player, flying).is_none() {
+        straight
+    } else {
+        flow_field
+            .direction(tiles, pos, flying)
+            .unwrap_or(if flying { straight } else { Vec2::ZERO })
+    }
+}
+
+pub fn wander(
+    mut query: Query<(&Transform, &mut Vel, &mut Wander, &Enemy, Option<&Grounded>)>,
+    player: Query<&Transform, With<PlayerEntity>>,
+    tiles: Res<Tiles>,
+    flow_field: Res<FlowField>,
+    time: Res<Time>,
+) {
+    let player_pos = player.single().translation.xy();
+    for (trans, mut vel, mut wander, enemy, grounded) in &mut query {
+        if enemy.stunned() {
+            continue;
+        }
+        if wander.timer > wander.period {
+            wander.timer = 0.;
+        }
+        if wander.timer == 0. {
+            let pos = trans.translation.xy();
+            let random_dir = Dir2::from_rng(&mut thread_rng()).as_vec2();
+            let player_dir =
+                towards_player(&tiles, &flow_field, pos, player_pos, grounded.is_none());
+            let mut dir = (random_dir + player_dir * wander.chase).normalize_or_zero();
+
+            // Turn back instead of floating into the room's edges
+            let max = tiles.px_size() - 12.;
+            if ((pos.y + dir.y * 24. < 10.) & (dir.y < 0.))
+                | ((pos.y + dir.y * 24. > max.y) & (dir.y > 0.))
+            {
+                dir.y *= -1.
+            }
+            if ((pos.x + dir.x * 24. < 10.) & (dir.x < 0.))
+                | ((pos.x + dir.x * 24. > max.x) & (dir.x > 0.))
+            {
+                dir.x *= -1.
+            }
+            vel.0 = dir;
+        }
+        vel.0 *= 1. - time.delta_seconds() * 1.0;
+        if (wander.timer < wander.burst) & (vel.length() != 0.) {
+            let dir = vel.normalize();
+            vel.0 += dir * time.delta_seconds() * wander.propulsion;
+        }
+        wander.timer += time.delta_seconds();
+    }
+}
+
+pub fn chase(
+    mut query: Query<(&Transform, &mut Vel, &Chase, &Enemy, Option<&Grounded>)>,
+    player: Query<&Transform, With<PlayerEntity>>,
+    tiles: Res<Tiles>,
+    flow_field: Res<FlowField>,
+    time: Res<Time>,
+) {
+    let player_pos = player.single().translation.xy();
+    for (trans, mut vel, chase, enemy, grounded) in &mut query {
+        if enemy.stunned() {
+            continue;
+        }
+        let pos = trans.translation.xy();
+        let dir = towards_player(&tiles, &flow_field, pos, player_pos, grounded.is_none());
+        vel.0 = vel
+            .0
+            .lerp(dir * chase.speed, (time.delta_seconds() * 6.).min(1.));
+    }
+}
+
+/// Moves every enemy by its velocity, colliding with the room
+pub fn move_enemies(
+    mut query: Query<(
+        &mut Transform,
+        &mut Vel,
+        &Enemy,
+        Option<&Grounded>,
+        Option<&Haste>,
+    )>,
+    tiles: Res<Tiles>,
+    pit_falling: Res<PitFalling>,
+    time: Res<Time>,
+) {
+    if time.delta_seconds() <= 0. {
+        return;
+    }
+    for (mut trans, mut vel, enemy, grounded, haste) in &mut query {
+        // Knockback carries walkers over pits, like the player, so they can fall in
+        let over_pits = grounded.is_none() | (enemy.stunned() & pit_falling.0);
+        // Goo and conveyors only affect enemies walking on them, same as the player
+        let ground = grounded.and(tiles.at(trans.translation.xy()));
+        let speed = haste.map_or(1., |haste| haste.0)
+            * if ground == Some(Tile::Goo) {
+                GOO_SLOWDOWN
+            } else {
+                1.
+            };
+        let push = match ground {
+            Some(Tile::Conveyor(dir)) => dir.as_vec2() * CONVEYOR_SPEED,
+            _ => Vec2::ZERO,
+        };
+        let movement = grid_collision(
+            &tiles,
+            trans.translation.xy(),
+            enemy.size,
+            (vel.0 * speed + push) * time.delta_seconds(),
+            over_pits,
+        );
+        if movement.is_nan() {
+            vel.0 = Vec2::ZERO;
+            continue;
+        }
+        trans.translation += movement.extend(0.);
+        vel.0 = (movement / time.delta_seconds() - push) / speed;
+    }
+}
+
+pub fn separation(
+    mut query: Query<(Entity, &mut Vel, &Enemy), With<Separation>>,
+    others: Query<(&Transform, &Enemy)>,
+    grid: Res<EnemyGrid>,
+) {
+    for (entity, mut vel, enemy) in &mut query {
+        let Ok((trans, _)) = others.get(entity) else {
+            continue;
+        };
+        let pos = trans.translation.xy();
+        for other_entity in grid.near(pos, enemy.size) {
+            let Ok((other, other_enemy)) = others.get(other_entity) else {
+                continue;
+            };
+            let other = other.translation.xy();
+            if (other_entity != entity) & (other != pos) {
+                let distance = other.distance(pos);
+                let direction = (pos - other).normalize();
+                vel.0 += direction * (enemy.size + other_enemy.size - distance).max(0.) / 2.4;
+            }
+        }
+    }
+}
+
+pub fn contact_damage(
+    mut commands: Commands,
+    query: Query<(&Transform, &Enemy), With<ContactDamage>>,
+    player: Query<&Transform, With<PlayerEntity>>,
+    grid: Res<EnemyGrid>,
+) {
+    let player_pos = player.single().translation.xy();
+    for entity in grid.near(player_pos, PLAYER_SIZE) {
+        let Ok((trans, enemy)) = query.get(entity) else {
+            continue;
+        };
+        let pos = trans.translation.xy();
+        if player_pos.distance(pos) < enemy.size + PLAYER_SIZE {
+            commands.trigger(HurtPlayer {
+                source: Some(pos),
+                unavoidable: false,
+            });
+        }
+    }
+}
+
+pub fn summoners(
+    mut commands: Commands,
+    mut query: Query<(&Transform, &mut Summoner, &Enemy)>,
+    state: Res<State<RoomState>>,
+    time: Res<Time>,
+) {
+    for (trans, mut summoner, enemy) in &mut query {
+        if enemy.stunned() {
+            continue;
+        }
+        summoner.timer -= time.delta_seconds();
+        if (summoner.timer < 0.) & (*state == RoomState::Fighting) {
+            commands.spawn((
+                Spawner::create(trans.translation.xy(), summoner.enemy.clone(), 0.),
+                Clearable,
+            ));
+            summoner.timer = summoner.interval;
+        }
+    }
+}
+
+pub fn deaths(
+    mut commands: Commands,
+    query: Query<
+        (
+            Entity,
+            &Transform,
+            &Enemy,
+            &Sprite,
+            &Handle<Image>,
+            Option<&Drops>,
+        ),
+        With<DeathOnZeroHealth>,
+    >,
+    handles: Res<Handles>,
+) {
+    for (entity, trans, enemy, sprite, texture, drops) in &query {
+        if enemy.health > 0. {
+            continue;
+        }
+        commands.entity(entity).despawn_recursive();
+        commands.trigger(EmitParticles {
+            pos: trans.translation.xy() + Vec2::Y * enemy.size,
+            burst: Burst::enemy_death(sprite.color),
+        });
+        // Flattened, darkened leftover until the room is left
+        commands.spawn((
+            Clearable,
+            Layer(-0.8),
+            SpriteBundle {
+                sprite: Sprite {
+                    anchor: sprite.anchor,
+                    color: Color::srgba(0.25, 0.15, 0.3, 0.7),
+                    flip_x: thread_rng().gen(),
+                    ..default()
+                },
+                texture: texture.clone(),
+                transform: trans.with_scale(trans.scale * vec3(1., 0.4, 1.)),
+                ..default()
+            },
+        ));
+        if let Some(drops) = drops {
+            spawn_drops(&mut commands, drops, trans.translation.xy(), &handles);
+        }
+        commands.trigger(Shake(0.25));
+        commands.spawn(AudioBundle {
+            source: handles.sfx_enemy_death.clone(),
+            settings: PlaybackSettings {
+                mode: bevy::audio::PlaybackMode::Despawn,
+                volume: bevy::audio::Volume::new(0.4),
+                ..default()
+            },
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::level::tests::{centre, room};
+
+    /// Pushes a walker from the middle of a floor cell towards the pit next to it
+    fn push_into_pit(stun: f32, pit_falling: bool) -> Vec2 {
+        let mut world = World::new();
+        world.insert_resource(room(&[".....", "..o..", "....."]));
+        world.insert_resource(PitFalling(pit_falling));
+        let mut time = Time::<()>::default();
+        time.advance_by(Duration::from_millis(100));
+        world.insert_resource(time);
+        let enemy = world
+            .spawn((
+                Transform::from_translation(centre(1, 1).extend(0.)),
+                Vel(Vec2::X * 100.),
+                Enemy {
+                    health: 1.,
+                    size: 3.,
+                    knockback: 1.,
+                    stun,
+                },
+                Grounded,
+            ))
+            .id();
+        world.run_system_once(move_enemies);
+        world.get::<Transform>(enemy).unwrap().translation.xy()
+    }
+
+    #[test]
+    fn walkers_stop_at_pits() {
+        let pos = push_into_pit(0., true);
+        assert_eq!(Tiles::cell_at(pos).x, 1);
+    }
+
+    #[test]
+    fn knockback_carries_walkers_over_pits() {
+        let pos = push_into_pit(0.5, true);
+        assert_eq!(Tiles::cell_at(pos).x, 2);
+    }
+
+    #[test]
+    fn pits_block_without_pit_falling() {
+        let pos = push_into_pit(0.5, false);
+        assert_eq!(Tiles::cell_at(pos).x, 1);
+    }
+}
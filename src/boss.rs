@@ -0,0 +1,173 @@
+use std::f32::consts::TAU;
+
+use bevy::{math::vec2, prelude::*, sprite::Anchor};
+
+use crate::{
+    camera::{MainCamera, Shake},
+    enemy::{Enemy, Spawner},
+    pattern::{Aim, Emitter, Pattern, Shape},
+    Clearable, Cycle,
+};
+
+const PHASES: usize = 3;
+const BAR_WIDTH: f32 = 120.;
+
+/// Changes attacks as its health goes down, getting tougher with every cycle
+#[derive(Component)]
+pub struct Boss {
+    summon: String,
+    max_health: f32,
+    phase: usize,
+    summon_timer: f32,
+}
+
+impl Boss {
+    pub fn new(summon: String) -> Self {
+        Self {
+            summon,
+            max_health: 0.,
+            phase: 0,
+            summon_timer: 0.,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct BossHealthBar;
+
+#[derive(Component)]
+pub struct BossHealthFill;
+
+fn difficulty(cycle: &Cycle) -> f32 {
+    1. + 0.15 * cycle.cycle as f32
+}
+
+fn phase_attack(phase: usize, difficulty: f32) -> Emitter {
+    let (mut pattern, interval) = match phase {
+        // Aimed fans
+        0 => (
+            Pattern::new(
+                Shape::Fan {
+                    count: 5,
+                    spread: 46.,
+                },
+                60.,
+            ),
+            1.6,
+        ),
+        // Rings with a gap to slip through
+        1 => (Pattern::new(Shape::Ring { count: 16, gap: 1 }, 48.), 2.),
+        // Double spiral
+        _ => {
+            let mut pattern = Pattern::new(Shape::Ring { count: 2, gap: 0 }, 54.);
+            pattern.aim = Aim::Angle(0.);
+            pattern.spin = 170. * difficulty;
+            (pattern, 0.15)
+        }
+    };
+    pattern.speed *= difficulty;
+    pattern.offset = 8.;
+    Emitter::new(pattern, interval / difficulty)
+}
+
+pub fn init_bosses(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Boss, &mut Enemy), Added<Boss>>,
+    cycle: Res<Cycle>,
+) {
+    for (entity, mut boss, mut enemy) in &mut query {
+        enemy.health *= 1. + 0.5 * cycle.cycle as f32;
+        boss.max_health = enemy.health;
+        boss.summon_timer = 4.;
+        let difficulty = difficulty(&cycle);
+        commands
+            .entity(entity)
+            .insert(phase_attack(0, difficulty).first(1.5 / difficulty));
+    }
+}
+
+pub fn bosses(
+    mut commands: Commands,
+    mut query: Query<(Entity, &Transform, &mut Boss, &Enemy)>,
+    cycle: Res<Cycle>,
+    time: Res<Time>,
+) {
+    for (entity, trans, mut boss, enemy) in &mut query {
+        let pos = trans.translation.xy();
+        let lost = 1. - enemy.health / boss.max_health;
+        let phase = ((lost * PHASES as f32) as usize).min(PHASES - 1);
+        if phase != boss.phase {
+            boss.phase = phase;
+            let difficulty = difficulty(&cycle);
+            commands
+                .entity(entity)
+                .insert(phase_attack(phase, difficulty).first(1. / difficulty));
+            commands.trigger(Shake(0.5));
+        }
+
+        // Calls for help in the later phases
+        boss.summon_timer -= time.delta_seconds();
+        if (boss.phase > 0) & (boss.summon_timer <= 0.) {
+            let count = 1 + cycle.cycle / 2;
+            for i in 0..count {
+                let offset = Vec2::from_angle(i as f32 / count as f32 * TAU) * 20.;
+                commands.spawn((
+                    Spawner::create(pos + offset, boss.summon.clone(), 0.),
+                    Clearable,
+                ));
+            }
+            boss.summon_timer = 8.;
+        }
+    }
+}
+
+pub fn boss_health_bar(
+    mut commands: Commands,
+    bosses: Query<(&Boss, &Enemy)>,
+    bar: Query<Entity, With<BossHealthBar>>,
+    mut fill: Query<&mut Transform, With<BossHealthFill>>,
+    camera: Query<Entity, With<MainCamera>>,
+) {
+    let Some((boss, enemy)) = bosses.iter().next() else {
+        for entity in &bar {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    };
+    if bar.is_empty() {
+        commands.entity(camera.single()).with_children(|b| {
+            b.spawn((
+                BossHealthBar,
+                SpatialBundle::from_transform(Transform::from_xyz(0., 80., -6.)),
+            ))
+            .with_children(|b| {
+                b.spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::BLACK,
+                        custom_size: Some(vec2(BAR_WIDTH + 2., 6.)),
+                        ..default()
+                    },
+                    ..default()
+                });
+                b.spawn((
+                    BossHealthFill,
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: Color::srgb(0.9, 0.2, 0.25),
+                            custom_size: Some(vec2(BAR_WIDTH, 4.)),
+                            anchor: Anchor::CenterLeft,
+                            ..default()
+                        },
+                        transform: Transform::from_xyz(-BAR_WIDTH / 2., 0., 0.1),
+                        ..default()
+                    },
+                ));
+            });
+        });
+    }
+    if boss.max_health > 0. {
+        for mut trans in &mut fill {
+            trans.scale.x = (enemy.health / boss.max_health).max(0.);
+        }
+    }
+}
@@ -1,34 +1,111 @@
-use bevy::prelude::*;
+use bevy::{ecs::system::EntityCommands, math::vec3, prelude::*};
 
 use crate::{
+    affix::Shielded,
+    behaviour::DeathOnZeroHealth,
     collision,
     enemy::Enemy,
-    level::Tiles,
+    hitstop::Hitstop,
+    interactive::hit_tile,
+    level::{Tile, Tiles},
+    particle::{Burst, EmitParticles},
     player::{HurtPlayer, PlayerEntity, PLAYER_SIZE},
-    Hurtable, Vel,
+    spatial::EnemyGrid,
+    Clearable, Handles, Hurtable, Layer, Vel,
 };
 
 const BULLET_SIZE: f32 = 1.5;
 const BULLET_DAMAGE: f32 = 1.;
+const KNOCKBACK: f32 = 60.;
+const HIT_STUN: f32 = 0.15;
 
 #[derive(Component)]
 pub struct Bullet {
     pub friendly: bool,
 }
 
+/// Speeds a bullet up, or slows it down with a negative `rate`, staying within `min..=max`
+#[derive(Component)]
+pub struct Accel {
+    pub rate: f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+/// Spawns a bullet with its sprite pointing along `vel`. Enemy bullets are tinted yellow.
+pub fn spawn_bullet<'a>(
+    commands: &'a mut Commands,
+    handles: &Handles,
+    pos: Vec2,
+    vel: Vec2,
+    friendly: bool,
+) -> EntityCommands<'a> {
+    let color = if friendly {
+        Color::WHITE
+    } else {
+        Color::hsv(57., 0.78, 1.)
+    };
+    let mut bullet = commands.spawn(());
+    bullet
+        .with_children(|b| {
+            b.spawn(SpriteBundle {
+                sprite: Sprite { color, ..default() },
+                texture: handles.bullet.clone(),
+                transform: Transform {
+                    translation: vec3(0., 9., 0.),
+                    rotation: Quat::from_rotation_z(vel.to_angle()),
+                    ..default()
+                },
+                ..default()
+            });
+        })
+        .insert((
+            Layer(0.0),
+            Transform::from_translation(pos.extend(0.)),
+            Clearable,
+            Vel(vel),
+            Bullet { friendly },
+            GlobalTransform::default(),
+            InheritedVisibility::default(),
+        ));
+    bullet
+}
+
 pub fn move_bullets(
     mut commands: Commands,
-    tiles: Res<Tiles>,
-    mut bullets: Query<(Entity, &mut Transform, &Vel, &Bullet)>,
-    mut enemies: Query<(&Transform, &mut Enemy, &mut Hurtable), Without<Bullet>>,
+    mut tiles: ResMut<Tiles>,
+    mut bullets: Query<(Entity, &mut Transform, &mut Vel, &Bullet, Option<&Accel>)>,
+    mut enemies: Query<
+        (
+            &Transform,
+            &mut Enemy,
+            &mut Vel,
+            &mut Hurtable,
+            Option<&Shielded>,
+            Has<DeathOnZeroHealth>,
+        ),
+        Without<Bullet>,
+    >,
     player: Query<&Transform, (With<PlayerEntity>, Without<Bullet>)>,
+    grid: Res<EnemyGrid>,
     time: Res<Time>,
 ) {
-    for (entity, mut trans, vel, bullet) in &mut bullets {
+    for (entity, mut trans, mut vel, bullet, accel) in &mut bullets {
+        if let Some(accel) = accel {
+            let speed =
+                (vel.length() + accel.rate * time.delta_seconds()).clamp(accel.min, accel.max);
+            vel.0 = vel.normalize_or_zero() * speed;
+        }
         let pos = trans.translation.xy();
         let movement = vel.0 * time.delta_seconds();
         if bullet.friendly {
-            for (enemy_pos, mut enemy, mut hurt) in &mut enemies {
+            let mut hit = false;
+            for enemy_entity in grid.near(pos, movement.length() + BULLET_SIZE) {
+                let Ok((enemy_pos, mut enemy, mut enemy_vel, mut hurt, shield, mortal)) =
+                    enemies.get_mut(enemy_entity)
+                else {
+                    continue;
+                };
                 if collision::with_ball(
                     enemy_pos.translation.xy(),
                     enemy.size,
@@ -37,12 +114,25 @@ pub fn move_bullets(
                     movement,
                 ) != movement
                 {
-                    enemy.health -= BULLET_DAMAGE;
-                    hurt.last_hit = 0.;
+                    let blocked = shield
+                        .is_some_and(|shield| shield.facing.dot(-vel.normalize_or_zero()) > 0.5);
+                    if !blocked {
+                        if mortal & (enemy.health > 0.) & (enemy.health <= BULLET_DAMAGE) {
+                            commands.trigger(Hitstop(0.06));
+                        }
+                        enemy.health -= BULLET_DAMAGE;
+                        enemy.stun = HIT_STUN;
+                        enemy_vel.0 += vel.normalize_or_zero() * KNOCKBACK * enemy.knockback;
+                        hurt.last_hit = 0.;
+                    }
                     commands.entity(entity).despawn_recursive();
+                    hit = true;
                     break;
                 }
             }
+            if hit {
+                continue;
+            }
         } else if collision::with_ball(
             player.single().translation.xy(),
             PLAYER_SIZE,
@@ -51,10 +141,23 @@ pub fn move_bullets(
             movement,
         ) != movement
         {
-            commands.trigger(HurtPlayer);
+            commands.trigger(HurtPlayer {
+                source: Some(pos),
+                unavoidable: false,
+            });
             commands.entity(entity).despawn_recursive();
         }
-        if movement != collision::grid_collision(&tiles, pos, BULLET_SIZE, movement, true) {
+        let tip = pos + movement + movement.normalize_or_zero() * BULLET_SIZE;
+        if let Some((cell, hit)) = tiles.raycast(pos, tip, true) {
+            commands.trigger(EmitParticles {
+                pos: hit,
+                burst: Burst::bullet_impact(),
+            });
+            // Only borrow mutably for tiles that can break, so misses don't count as changes
+            let breakable = tiles.contains(cell) && matches!(tiles[cell], Tile::Cracked(_));
+            if bullet.friendly & breakable {
+                hit_tile(&mut commands, &mut tiles, cell);
+            }
             commands.entity(entity).despawn_recursive();
         }
         trans.translation += movement.extend(0.);
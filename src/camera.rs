@@ -0,0 +1,107 @@
+use bevy::{math::vec2, prelude::*};
+use rand::prelude::*;
+
+use crate::{level::Tiles, player::PlayerEntity, transition::RoomTransition};
+
+// The bottom and left edges of a room are hidden behind the walls anyway,
+// so the view is shifted up and right a bit
+const VIEW_OFFSET: Vec2 = vec2(5., 5.);
+const MAX_SHAKE: f32 = 6.;
+
+#[derive(Component)]
+pub struct MainCamera;
+
+#[derive(Component)]
+pub struct CameraController {
+    /// Whether to follow the player in rooms that don't fit on screen
+    pub follow: bool,
+    pub trauma: f32,
+    focus: Vec2,
+    /// Jump straight to the target next frame instead of easing there
+    snap: bool,
+}
+
+impl Default for CameraController {
+    fn default() -> Self {
+        Self {
+            follow: true,
+            trauma: 0.,
+            focus: vec2(101., 101.),
+            snap: true,
+        }
+    }
+}
+
+/// Adds trauma to the camera, which decays over time
+#[derive(Event)]
+pub struct Shake(pub f32);
+
+pub fn add_trauma(trigger: Trigger<Shake>, mut camera: Query<&mut CameraController>) {
+    for mut camera in &mut camera {
+        camera.trauma = (camera.trauma + trigger.event().0).min(1.);
+    }
+}
+
+/// Sent on entering a new room, so the camera doesn't pan over from the last one
+#[derive(Event)]
+pub struct RoomEntered;
+
+pub fn snap_camera(_: Trigger<RoomEntered>, mut camera: Query<&mut CameraController>) {
+    for mut camera in &mut camera {
+        camera.snap = true;
+    }
+}
+
+pub fn update_camera(
+    mut camera: Query<
+        (
+            &mut Transform,
+            &OrthographicProjection,
+            &mut CameraController,
+        ),
+        With<MainCamera>,
+    >,
+    player: Query<&Transform, (With<PlayerEntity>, Without<MainCamera>)>,
+    tiles: Res<Tiles>,
+    transition: Option<Res<RoomTransition>>,
+    time: Res<Time>,
+) {
+    let Ok((mut trans, projection, mut camera)) = camera.get_single_mut() else {
+        return;
+    };
+    let view = projection.area.size();
+    let min = VIEW_OFFSET * 2.;
+    let max = tiles.px_size();
+    let mut target = (min + max) / 2.;
+    // Scroll along axes where the room doesn't fit on screen
+    if let (true, Ok(player)) = (camera.follow, player.get_single()) {
+        for axis in 0..2 {
+            if view[axis] < max[axis] - min[axis] {
+                target[axis] = player.translation[axis]
+                    .clamp(min[axis] + view[axis] / 2., max[axis] - view[axis] / 2.);
+            }
+        }
+    }
+    if camera.snap {
+        camera.focus = target;
+        camera.snap = false;
+    } else {
+        camera.focus = camera
+            .focus
+            .lerp(target, 1. - (-8. * time.delta_seconds()).exp());
+    }
+
+    camera.trauma = (camera.trauma - time.delta_seconds() * 1.5).max(0.);
+    let shake = vec2(
+        thread_rng().gen_range(-1. ..1.),
+        thread_rng().gen_range(-1. ..1.),
+    ) * camera.trauma.powi(2)
+        * MAX_SHAKE;
+
+    let slide = transition.map_or(Vec2::ZERO, |t| t.slide_offset(view));
+
+    // Snap to whole pixels to keep the pixel art crisp
+    trans.translation = (camera.focus + shake + slide)
+        .round()
+        .extend(trans.translation.z);
+}
@@ -0,0 +1,139 @@
+use bevy::{
+    math::uvec2,
+    prelude::*,
+    render::{
+        render_resource::{
+            Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+        },
+        view::RenderLayers,
+    },
+    window::PrimaryWindow,
+};
+
+/// Size of the low-resolution image the game is rendered to
+pub const RESOLUTION: UVec2 = uvec2(192, 176);
+
+#[derive(Resource)]
+pub struct Canvas {
+    pub image: Handle<Image>,
+    /// If true, the canvas keeps its size and is surrounded by black bars.
+    /// Otherwise it grows to fill the window, showing more of the room.
+    pub letterbox: bool,
+    window_size: UVec2,
+}
+
+/// Displays the canvas in the window
+#[derive(Component)]
+pub struct CanvasSprite;
+
+pub fn setup_canvas(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let size = Extent3d {
+        width: RESOLUTION.x,
+        height: RESOLUTION.y,
+        depth_or_array_layers: 1,
+    };
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..default()
+    };
+    image.resize(size);
+    let image = images.add(image);
+
+    commands.spawn((
+        Camera2dBundle {
+            camera: Camera {
+                order: 1,
+                ..default()
+            },
+            ..default()
+        },
+        RenderLayers::layer(1),
+    ));
+    commands.spawn((
+        CanvasSprite,
+        SpriteBundle {
+            texture: image.clone(),
+            ..default()
+        },
+        RenderLayers::layer(1),
+    ));
+    commands.insert_resource(Canvas {
+        image,
+        letterbox: true,
+        window_size: UVec2::ZERO,
+    });
+}
+
+/// Upscales the canvas by the largest integer factor that fits into the window.
+/// F2 switches between letterboxing and filling the window.
+pub fn fit_canvas(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    mut canvas: ResMut<Canvas>,
+    mut sprite: Query<&mut Transform, With<CanvasSprite>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let Ok(window) = window.get_single() else {
+        return;
+    };
+    let window_size = window.physical_size();
+    let toggle = keyboard_input.just_pressed(KeyCode::F2);
+    if toggle {
+        canvas.letterbox = !canvas.letterbox;
+    }
+    if ((window_size == canvas.window_size) & !toggle) | (window_size.min_element() == 0) {
+        return;
+    }
+    canvas.window_size = window_size;
+
+    let scale = (window_size / RESOLUTION).min_element().max(1);
+    let size = if canvas.letterbox {
+        RESOLUTION
+    } else {
+        // Keep the size even so that the camera stays aligned to the pixel grid
+        (window_size / scale / 2 * 2).max(RESOLUTION)
+    };
+    if let Some(image) = images.get_mut(&canvas.image) {
+        image.resize(Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        });
+    }
+
+    // The sprite is positioned in logical pixels, but should line up with physical ones
+    let mut trans = sprite.single_mut();
+    let odd = window_size.saturating_sub(size * scale) % 2;
+    trans.translation = (odd.as_vec2() * 0.5 / window.scale_factor()).extend(0.);
+    trans.scale = Vec3::splat(scale as f32 / window.scale_factor());
+}
+
+/// Rounds the positions of sprites that moved to whole pixels after transform propagation
+pub fn snap_to_pixels(
+    mut query: Query<
+        &mut GlobalTransform,
+        (
+            Changed<GlobalTransform>,
+            With<Sprite>,
+            Without<CanvasSprite>,
+        ),
+    >,
+) {
+    for mut global in &mut query {
+        let mut trans = global.compute_transform();
+        trans.translation.x = trans.translation.x.round();
+        trans.translation.y = trans.translation.y.round();
+        *global = trans.into();
+    }
+}
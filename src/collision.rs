@@ -3,7 +3,7 @@ use bevy::{
     prelude::*,
 };
 
-use crate::level::{Tile, Tiles, CELL_SIZE};
+use crate::level::{Tiles, CELL_SIZE};
 
 pub fn grid_collision(
     tiles: &Tiles,
@@ -20,11 +20,10 @@ pub fn grid_collision(
         .as_ivec2();
     for x in min.x..=max.x {
         for y in min.y..=max.y {
-            if !(0..16).contains(&x) | !(0..16).contains(&y) {
+            if !tiles.contains(ivec2(x, y)) {
                 return Vec2::ZERO;
             }
-            let cell = tiles[ivec2(x, y)];
-            if (cell == Tile::Wall) | ((cell == Tile::Pit) & !flying) {
+            if tiles[ivec2(x, y)].blocks(flying) {
                 movement = with_cell(ivec2(x, y), pos, radius, movement)
             }
         }
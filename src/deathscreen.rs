@@ -1,6 +1,9 @@
 use bevy::{math::vec3, prelude::*};
 
-use crate::{ldtk::LdtkProject, player::Player, Clearable, Cycle, Handles, RoomState};
+use crate::{
+    camera::MainCamera, ldtk::LdtkProject, player::Player, transition::RoomTransition, Clearable,
+    Cycle, Handles, RoomState,
+};
 
 #[derive(Resource)]
 pub struct DeathTimer(f32);
@@ -27,26 +30,30 @@ pub fn death_screen(
     ldtk: Res<LdtkProject>,
     clearable: Query<Entity, With<Clearable>>,
     mut respawn_hint: Query<&mut Sprite, (With<RespawnHint>, Without<Background>)>,
+    camera: Query<Entity, With<MainCamera>>,
 ) {
+    let camera = camera.single();
     let Some(mut timer) = timer else {
         commands.insert_resource(DeathTimer(0.));
-        commands.spawn((
-            Background,
-            DespawnOnRespawn,
-            SpriteBundle {
-                texture: handles.black.clone(),
-                transform: Transform {
-                    translation: vec3(0., 0., 10.),
-                    scale: vec3(400., 400., 1.),
+        commands
+            .spawn((
+                Background,
+                DespawnOnRespawn,
+                SpriteBundle {
+                    texture: handles.black.clone(),
+                    transform: Transform {
+                        translation: vec3(0., 0., 0.),
+                        scale: vec3(400., 400., 1.),
+                        ..default()
+                    },
+                    sprite: Sprite {
+                        color: Color::srgba(0., 0., 0., 0.),
+                        ..default()
+                    },
                     ..default()
                 },
-                sprite: Sprite {
-                    color: Color::srgba(0., 0., 0., 0.),
-                    ..default()
-                },
-                ..default()
-            },
-        ));
+            ))
+            .set_parent(camera);
         return;
     };
 
@@ -62,56 +69,62 @@ pub fn death_screen(
     background.alpha = (timer.0 * 0.2).powf(0.3).min(1.);
 
     if (timer.0 - time.delta_seconds()..timer.0).contains(&3.) {
-        commands.spawn((
-            DespawnOnRespawn,
-            SpriteBundle {
-                texture: handles.ouroboros.clone(),
-                transform: Transform {
-                    translation: vec3(101., 101., 11.),
-                    scale: vec3(2., 2., 1.),
-                    ..default()
-                },
-                ..default()
-            },
-        ));
-        commands.spawn((
-            DespawnOnRespawn,
-            Text2dBundle {
-                text: Text {
-                    sections: vec![TextSection {
-                        value: format!("{}", cycle.cycle),
-                        style: TextStyle {
-                            font: handles.font_score.clone(),
-                            font_size: 16.,
-                            color: Color::WHITE,
-                        },
-                    }],
+        commands
+            .spawn((
+                DespawnOnRespawn,
+                SpriteBundle {
+                    texture: handles.ouroboros.clone(),
+                    transform: Transform {
+                        translation: vec3(0., 0., 1.),
+                        scale: vec3(2., 2., 1.),
+                        ..default()
+                    },
                     ..default()
                 },
-                transform: Transform {
-                    translation: vec3(101., 101., 11.),
-                    scale: vec3(2., 2., 1.),
+            ))
+            .set_parent(camera);
+        commands
+            .spawn((
+                DespawnOnRespawn,
+                Text2dBundle {
+                    text: Text {
+                        sections: vec![TextSection {
+                            value: format!("{}", cycle.cycle),
+                            style: TextStyle {
+                                font: handles.font_score.clone(),
+                                font_size: 16.,
+                                color: Color::WHITE,
+                            },
+                        }],
+                        ..default()
+                    },
+                    transform: Transform {
+                        translation: vec3(0., 0., 1.),
+                        scale: vec3(2., 2., 1.),
+                        ..default()
+                    },
                     ..default()
                 },
-                ..default()
-            },
-        ));
+            ))
+            .set_parent(camera);
     }
 
     if (timer.0 - time.delta_seconds()..timer.0).contains(&10.) {
-        commands.spawn((
-            DespawnOnRespawn,
-            RespawnHint,
-            SpriteBundle {
-                texture: handles.key_enter.clone(),
-                transform: Transform::from_xyz(101., 40., 11.),
-                sprite: Sprite {
-                    color: Color::srgba(0., 0., 0., 0.),
+        commands
+            .spawn((
+                DespawnOnRespawn,
+                RespawnHint,
+                SpriteBundle {
+                    texture: handles.key_enter.clone(),
+                    transform: Transform::from_xyz(0., -61., 1.),
+                    sprite: Sprite {
+                        color: Color::srgba(0., 0., 0., 0.),
+                        ..default()
+                    },
                     ..default()
                 },
-                ..default()
-            },
-        ));
+            ))
+            .set_parent(camera);
     }
 
     if let Ok(mut sprite) = respawn_hint.get_single_mut() {
@@ -120,7 +133,7 @@ pub fn death_screen(
 
     if (timer.0 > 3.) & keyboard_input.just_pressed(KeyCode::Enter) {
         for entity in &despawn {
-            commands.entity(entity).despawn();
+            commands.entity(entity).despawn_recursive();
         }
         commands.remove_resource::<DeathTimer>();
         // Reset
@@ -130,6 +143,7 @@ pub fn death_screen(
         for entity in &clearable {
             commands.entity(entity).despawn_recursive()
         }
+        commands.insert_resource(RoomTransition::fade_in());
         next_roomstate.set(RoomState::Fighting);
     }
 }
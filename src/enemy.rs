@@ -1,47 +1,41 @@
 use bevy::{
-    math::{ivec2, vec2, vec3},
+    math::{ivec2, vec2},
     prelude::*,
 };
 use rand::prelude::*;
 
 use crate::{
+    affix::Affixes,
+    archetype::EnemyArchetypes,
     aseprite::{Animation, AnimationData},
-    bullet::Bullet,
     collision::grid_collision,
-    level::{Tile, Tiles, CELL_SIZE},
-    player::{HurtPlayer, PlayerEntity, PLAYER_SIZE},
-    Clearable, Cycle, Handles, Hurtable, Layer, RoomState, Vel,
+    entities::{field_or_warn, SpawnContext},
+    ldtk::{EntityInstance, EnumValue, Fields, LdtkProject},
+    level::{current_level, Tile, Tiles, CELL_SIZE},
+    particle::{Burst, EmitParticles},
+    pickup::Drops,
+    player::PlayerEntity,
+    Clearable, Cycle, Handles, Hurtable, Layer, RoomKind, Vel,
 };
 
 static FLOATER_SIZE: f32 = 5.;
+/// In cells, how close to the player randomly placed enemies may be summoned
+static MIN_SPAWN_DISTANCE: f32 = 3.;
 
 #[derive(Component)]
 pub struct Enemy {
     pub health: f32,
     pub size: f32,
+    /// How much of a hit's impulse moves it, less with knockback resistance
+    pub knockback: f32,
+    /// Seconds left of being stunned by a hit, pausing its behaviours
+    pub stun: f32,
 }
 
-#[derive(Component)]
-pub struct FloaterA {
-    movement_timer: f32,
-}
-
-#[derive(Component)]
-pub struct FloaterB {
-    movement_timer: f32,
-}
-
-#[derive(Component)]
-pub struct Summoner {
-    movement_timer: f32,
-    summon_timer: f32,
-}
-
-#[derive(Clone, Copy)]
-pub enum EnemyKind {
-    A,
-    B,
-    Summoner,
+impl Enemy {
+    pub fn stunned(&self) -> bool {
+        self.stun > 0.
+    }
 }
 
 #[derive(Component)]
@@ -49,11 +43,12 @@ pub struct Spawner {
     timer: f32,
     enemy: Entity,
     summon_occluder: Entity,
-    kind: EnemyKind,
+    /// Name of the enemy archetype
+    kind: String,
 }
 
 impl Spawner {
-    pub fn create(pos: Vec2, kind: EnemyKind, delay: f32) -> impl Bundle {
+    pub fn create(pos: Vec2, kind: String, delay: f32) -> impl Bundle {
         (
             Self {
                 timer: delay + 1.25,
@@ -66,25 +61,149 @@ impl Spawner {
     }
 }
 
-pub fn spawn_enemies(mut commands: Commands, tiles: Res<Tiles>, cycle: Res<Cycle>) {
-    // Don't spawn enemies in the very first room
-    if (cycle.current_room == 0) & (cycle.cycle == 0) {
+#[derive(Component)]
+pub struct EnemySpawnPoint {
+    kind: String,
+    delay: f32,
+    wave: i32,
+}
+
+pub fn spawn_enemy_spawn_point(
+    commands: &mut Commands,
+    entity: &EntityInstance,
+    ctx: &SpawnContext,
+) {
+    let EnumValue(kind) = field_or_warn(entity, "Kind", EnumValue("FloaterB".to_owned()));
+    commands.spawn((
+        EnemySpawnPoint {
+            kind,
+            delay: field_or_warn(entity, "Delay", 2.),
+            wave: field_or_warn(entity, "Wave", 0),
+        },
+        Transform::from_translation(ctx.pos.extend(0.)),
+        Clearable,
+    ));
+}
+
+/// Authored waves of the current room, each started once the previous one is cleared
+#[derive(Resource, Default)]
+pub struct Waves {
+    waves: Vec<Vec<(Vec2, String, f32)>>,
+    /// Time between clearing a wave and the next one starting
+    pauses: Vec<f32>,
+    next: usize,
+    timer: f32,
+    /// A wave was started this tick, and its spawners may not exist yet
+    starting: bool,
+}
+
+impl Waves {
+    pub fn finished(&self) -> bool {
+        (self.next == self.waves.len()) & !self.starting
+    }
+
+    /// Time to wait before the next wave, the first one starting right away
+    fn pause(&self) -> f32 {
+        match self.next.checked_sub(1) {
+            Some(cleared) => self.pauses.get(cleared).copied().unwrap_or(1.),
+            None => 0.,
+        }
+    }
+}
+
+pub fn spawn_enemies(
+    mut commands: Commands,
+    tiles: Res<Tiles>,
+    cycle: Res<Cycle>,
+    ldtk: Res<LdtkProject>,
+    points: Query<(&Transform, &EnemySpawnPoint)>,
+    player: Query<&Transform, With<PlayerEntity>>,
+    handles: Res<Handles>,
+    archetypes: Res<Assets<EnemyArchetypes>>,
+) {
+    let archetypes = archetypes.get(&handles.enemies).unwrap();
+    if cycle.room_kind() == Some(RoomKind::Boss) {
+        commands.insert_resource(Waves::default());
+        commands.spawn((
+            Spawner::create(tiles.px_size() / 2., "Boss".to_owned(), 1.),
+            Clearable,
+        ));
         return;
     }
 
+    // Don't spawn enemies in the very first room or rest rooms
+    if cycle.is_start() | (cycle.room_kind() == Some(RoomKind::Rest)) {
+        commands.insert_resource(Waves::default());
+        return;
+    }
+
+    if !points.is_empty() {
+        let mut points = points.iter().collect::<Vec<_>>();
+        points.sort_by_key(|(_, point)| point.wave);
+        let mut waves = Vec::<Vec<_>>::new();
+        let mut last_wave = None;
+        for (trans, point) in points {
+            if last_wave != Some(point.wave) {
+                waves.push(Vec::new());
+                last_wave = Some(point.wave);
+            }
+            waves.last_mut().unwrap().push((
+                trans.translation.xy(),
+                point.kind.clone(),
+                point.delay,
+            ));
+        }
+        let level = current_level(&ldtk, &cycle);
+        let pauses = level
+            .field_or("WavePauses", Vec::new())
+            .unwrap_or_else(|err| {
+                warn!("{}: {err}", level.identifier);
+                Vec::new()
+            });
+        commands.insert_resource(Waves {
+            waves,
+            pauses,
+            next: 0,
+            timer: 0.,
+            starting: false,
+        });
+        return;
+    }
+    commands.insert_resource(Waves::default());
+
     let mut floor = Vec::new();
-    for x in 0..16 {
-        for y in 0..16 {
+    let mut far = Vec::new();
+    let mut in_sight = Vec::new();
+    for x in 0..tiles.size.x {
+        for y in 0..tiles.size.y {
             // Free & not occluded from vision
-            if (tiles[ivec2(x, y)] == Tile::Floor)
-                & (y < 2
-                    || (tiles[ivec2(x, y - 1)] != Tile::Wall)
-                        & (tiles[ivec2(x, y - 2)] != Tile::Wall))
+            let cell = ivec2(x, y);
+            if (tiles[cell] != Tile::Floor) | tiles.behind_wall(cell) {
+                continue;
+            }
+            floor.push(cell);
+            let center = (cell.as_vec2() + 0.5) * CELL_SIZE;
+            if player.iter().any(|player| {
+                player.translation.xy().distance(center) < MIN_SPAWN_DISTANCE * CELL_SIZE
+            }) {
+                continue;
+            }
+            far.push(cell);
+            if player
+                .iter()
+                .all(|player| tiles.line_of_sight(player.translation.xy(), center))
             {
-                floor.push(ivec2(x, y));
+                in_sight.push(cell);
             }
         }
     }
+    // Prefer spots the player can see but isn't right next to, unless the room is too
+    // cluttered
+    if !in_sight.is_empty() {
+        floor = in_sight;
+    } else if !far.is_empty() {
+        floor = far;
+    }
     for i in 0..4 + cycle.cycle - cycle.rooms[cycle.current_room].difficulty / 2 {
         let delay = 2. + 0.3 * i as f32;
         let tile_center =
@@ -99,38 +218,65 @@ pub fn spawn_enemies(mut commands: Commands, tiles: Res<Tiles>, cycle: Res<Cycle
             ) * CELL_SIZE,
             false,
         );
-        let kinds: &[_] = match cycle.cycle {
-            0 => &[(EnemyKind::B, 1.)],
-            1 => &[(EnemyKind::A, 1.), (EnemyKind::B, 1.)],
-            _ => &[
-                (EnemyKind::Summoner, 0.5),
-                (EnemyKind::A, 1.),
-                (EnemyKind::B, 1.),
-            ],
-        };
+        let kind = archetypes
+            .spawn_table(cycle.cycle)
+            .choose_weighted(&mut thread_rng(), |item| item.1)
+            .unwrap()
+            .0
+            .clone();
         commands.spawn((
-            Spawner::create(
-                tile_center + offset,
-                kinds
-                    .choose_weighted(&mut thread_rng(), |item| item.1)
-                    .unwrap()
-                    .0,
-                delay,
-            ),
+            Spawner::create(tile_center + offset, kind, delay),
+            Affixes::roll(archetypes, &cycle),
             Clearable,
         ));
     }
 }
 
+pub fn waves(
+    mut commands: Commands,
+    mut waves: ResMut<Waves>,
+    enemies: Query<(), Or<(With<Enemy>, With<Spawner>)>>,
+    cycle: Res<Cycle>,
+    handles: Res<Handles>,
+    archetypes: Res<Assets<EnemyArchetypes>>,
+    time: Res<Time>,
+) {
+    waves.starting = false;
+    if waves.finished() | !enemies.is_empty() {
+        return;
+    }
+    waves.timer += time.delta_seconds();
+    if waves.timer < waves.pause() {
+        return;
+    }
+    waves.timer = 0.;
+    let archetypes = archetypes.get(&handles.enemies).unwrap();
+    for (pos, kind, delay) in &waves.waves[waves.next] {
+        commands.spawn((
+            Spawner::create(*pos, kind.clone(), *delay),
+            Affixes::roll(archetypes, &cycle),
+            Clearable,
+        ));
+    }
+    waves.next += 1;
+    waves.starting = true;
+}
+
 pub fn spawners(
     mut commands: Commands,
-    mut spawners: Query<(Entity, &Transform, &mut Spawner)>,
+    mut spawners: Query<(Entity, &Transform, &mut Spawner, Option<&Affixes>)>,
     mut sprites: Query<&mut Sprite>,
     time: Res<Time>,
     handles: Res<Handles>,
     ani_data: Res<Assets<AnimationData>>,
+    archetypes: Res<Assets<EnemyArchetypes>>,
 ) {
-    for (entity, trans, mut spawner) in &mut spawners {
+    let archetypes = archetypes.get(&handles.enemies).unwrap();
+    for (entity, trans, mut spawner, affixes) in &mut spawners {
+        let Some(archetype) = archetypes.get(&spawner.kind) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
         let step = spawner.timer - time.delta_seconds()..spawner.timer;
         spawner.timer -= time.delta_seconds();
         if step.contains(&1.2) {
@@ -142,6 +288,10 @@ pub fn spawners(
                 },
                 Animation::new(handles.summon.clone(), false),
             ));
+            commands.trigger(EmitParticles {
+                pos: trans.translation.xy(),
+                burst: Burst::summon(),
+            });
         }
         if step.contains(&1.0) {
             commands.spawn(AudioBundle {
@@ -165,18 +315,9 @@ pub fn spawners(
                                 color: Color::hsla(300., 1., 0.85, 1.),
                                 ..default()
                             },
-                            texture: match spawner.kind {
-                                EnemyKind::A | EnemyKind::B => {
-                                    ani_data.get(&handles.floater_occluded).unwrap().frames[0]
-                                        .0
-                                        .clone()
-                                }
-                                EnemyKind::Summoner => {
-                                    ani_data.get(&handles.summoner_occluded).unwrap().frames[0]
-                                        .0
-                                        .clone()
-                                }
-                            },
+                            texture: ani_data.get(&archetype.occluded).unwrap().frames[0]
+                                .0
+                                .clone(),
                             transform: Transform::from_xyz(0., 0., 0.0001),
                             ..default()
                         },))
@@ -189,20 +330,13 @@ pub fn spawners(
                     SpriteBundle {
                         sprite: Sprite {
                             anchor: bevy::sprite::Anchor::BottomCenter,
+                            color: archetype.color,
                             ..default()
                         },
-                        texture: match spawner.kind {
-                            EnemyKind::A => ani_data.get(&handles.floater_a).unwrap().frames[0]
-                                .0
-                                .clone(),
-                            EnemyKind::B => ani_data.get(&handles.floater_b).unwrap().frames[0]
-                                .0
-                                .clone(),
-                            EnemyKind::Summoner => {
-                                ani_data.get(&handles.summoner).unwrap().frames[0].0.clone()
-                            }
-                        },
-                        transform: *trans,
+                        texture: ani_data.get(&archetype.animation).unwrap().frames[0]
+                            .0
+                            .clone(),
+                        transform: trans.with_scale(Vec3::splat(archetype.scale)),
                         ..default()
                     },
                 ))
@@ -216,333 +350,50 @@ pub fn spawners(
         }
         if step.contains(&0.) {
             commands.entity(entity).despawn();
-            match spawner.kind {
-                EnemyKind::A => {
-                    commands
-                        .entity(spawner.summon_occluder)
-                        .insert(Animation::new(handles.floater_occluded.clone(), true));
-                    commands.entity(spawner.enemy).insert((
-                        FloaterA { movement_timer: 0. },
-                        Enemy {
-                            health: 3.,
-                            size: FLOATER_SIZE,
-                        },
-                        Hurtable {
-                            last_hit: f32::INFINITY,
-                            indicator: spawner.summon_occluder,
-                        },
-                        Animation::new(handles.floater_a.clone(), true),
-                    ));
-                }
-                EnemyKind::B => {
-                    commands
-                        .entity(spawner.summon_occluder)
-                        .insert(Animation::new(handles.floater_occluded.clone(), true));
-                    commands.entity(spawner.enemy).insert((
-                        FloaterB { movement_timer: 0. },
-                        Enemy {
-                            health: 3.,
-                            size: FLOATER_SIZE,
-                        },
-                        Hurtable {
-                            last_hit: f32::INFINITY,
-                            indicator: spawner.summon_occluder,
-                        },
-                        Animation::new(handles.floater_b.clone(), true),
-                    ));
-                }
-                EnemyKind::Summoner => {
-                    commands
-                        .entity(spawner.summon_occluder)
-                        .insert(Animation::new(handles.summoner_occluded.clone(), true));
-                    commands.entity(spawner.enemy).insert((
-                        Summoner {
-                            movement_timer: 0.,
-                            summon_timer: 9.,
-                        },
-                        Enemy {
-                            health: 3.,
-                            size: FLOATER_SIZE,
-                        },
-                        Hurtable {
-                            last_hit: f32::INFINITY,
-                            indicator: spawner.summon_occluder,
-                        },
-                        Animation::new(handles.summoner.clone(), true),
-                    ));
-                }
-            }
-        }
-    }
-}
-
-pub fn floater_a(
-    mut commands: Commands,
-    mut floaters: Query<(Entity, &mut Vel, &mut FloaterA, &Enemy)>,
-    mut transform: Query<&mut Transform, (With<Enemy>, Without<PlayerEntity>)>,
-    player: Query<&Transform, With<PlayerEntity>>,
-    tiles: Res<Tiles>,
-    time: Res<Time>,
-    handles: Res<Handles>,
-) {
-    const PROPULSION: f32 = 70.;
-    for (entity, mut vel, mut floater, enemy) in &mut floaters {
-        let mut trans = transform.get_mut(entity).unwrap();
-        if floater.movement_timer == 0. {
-            let mut dir = Dir2::from_rng(&mut thread_rng()).as_vec2();
-            if ((trans.translation.y + dir.y * 24. < 10.) & (dir.y < 0.))
-                | ((trans.translation.y + dir.y * 24. > 180.) & (dir.y > 0.))
-            {
-                dir.y *= -1.
-            }
-            if ((trans.translation.x + dir.x * 24. < 10.) & (dir.x < 0.))
-                | ((trans.translation.x + dir.x * 24. > 180.) & (dir.x > 0.))
-            {
-                dir.x *= -1.
-            }
-            vel.0 = dir;
-        }
-        vel.0 *= 1. - time.delta_seconds() * 1.0;
-        if (floater.movement_timer < 0.8) & (vel.length() != 0.) {
-            let dir = vel.normalize();
-            vel.0 += dir * time.delta_seconds() * PROPULSION;
-        }
-        floater.movement_timer += time.delta_seconds();
-        let movement = vel.0 * time.delta_seconds();
-        let movement = grid_collision(&tiles, trans.translation.xy(), FLOATER_SIZE, movement, true);
-        trans.translation += movement.extend(0.);
-        let pos = trans.translation.xy();
-        vel.0 = movement / time.delta_seconds();
-        for other in &transform {
-            let other = other.translation.xy();
-            if other != pos {
-                let distance = other.distance(pos);
-                let direction = (pos - other).normalize();
-                vel.0 += direction * (2. * FLOATER_SIZE - distance).max(0.) / 2.4;
-            }
-        }
-
-        let player_pos = player.single();
-        if player_pos.translation.xy().distance(pos) < FLOATER_SIZE + PLAYER_SIZE {
-            commands.trigger(HurtPlayer);
-        }
-
-        if (floater.movement_timer - time.delta_seconds()..floater.movement_timer).contains(&3.)
-            & thread_rng().gen_bool(0.7)
-        {
-            let dir = (player_pos.translation.xy() - pos).normalize();
             commands
-                .spawn(())
-                .with_children(|b| {
-                    b.spawn(SpriteBundle {
-                        sprite: Sprite {
-                            color: Color::hsv(57., 0.78, 1.),
-                            ..default()
-                        },
-                        texture: handles.bullet.clone(),
-                        transform: Transform {
-                            translation: vec3(0., 9., 0.),
-                            rotation: Quat::from_rotation_z(dir.to_angle()),
-                            ..default()
-                        },
-                        ..default()
-                    });
-                })
-                .insert((
-                    Layer(0.0),
-                    Transform::from_translation((pos + dir * 5.).extend(0.)),
-                    Clearable,
-                    Vel(dir * 80.),
-                    Bullet { friendly: false },
-                    GlobalTransform::default(),
-                    InheritedVisibility::default(),
-                ));
-        }
-
-        if floater.movement_timer > 4. {
-            floater.movement_timer = 0.
-        }
-
-        if enemy.health <= 0. {
-            commands.entity(entity).despawn_recursive();
-            commands.spawn(AudioBundle {
-                source: handles.sfx_enemy_death.clone(),
-                settings: PlaybackSettings {
-                    mode: bevy::audio::PlaybackMode::Despawn,
-                    volume: bevy::audio::Volume::new(0.4),
-                    ..default()
+                .entity(spawner.summon_occluder)
+                .insert(Animation::new(archetype.occluded.clone(), true));
+            let no_affixes = Affixes::default();
+            let affixes = affixes.unwrap_or(&no_affixes);
+            let mut enemy = commands.entity(spawner.enemy);
+            enemy.insert((
+                Enemy {
+                    health: archetype.health * affixes.health_factor(),
+                    size: archetype.size,
+                    knockback: 1. - archetype.knockback_resistance,
+                    stun: 0.,
                 },
-            });
-        }
-    }
-}
-
-pub fn floater_b(
-    mut commands: Commands,
-    mut floaters: Query<(Entity, &mut Vel, &mut FloaterB, &Enemy)>,
-    mut transform: Query<&mut Transform, (With<Enemy>, Without<PlayerEntity>)>,
-    player: Query<&Transform, With<PlayerEntity>>,
-    tiles: Res<Tiles>,
-    time: Res<Time>,
-    handles: Res<Handles>,
-) {
-    const PROPULSION: f32 = 80.;
-    let player_pos = player.single();
-    for (entity, mut vel, mut floater, enemy) in &mut floaters {
-        let mut trans = transform.get_mut(entity).unwrap();
-        if floater.movement_timer == 0. {
-            let random_dir = Dir2::from_rng(&mut thread_rng()).as_vec2();
-            let player_dir =
-                (player_pos.translation.xy() - trans.translation.xy()).normalize_or_zero();
-            let mut dir = (random_dir + player_dir * 3.).normalize_or_zero();
-
-            if ((trans.translation.y + dir.y * 24. < 10.) & (dir.y < 0.))
-                | ((trans.translation.y + dir.y * 24. > 180.) & (dir.y > 0.))
-            {
-                dir.y *= -1.
-            }
-            if ((trans.translation.x + dir.x * 24. < 10.) & (dir.x < 0.))
-                | ((trans.translation.x + dir.x * 24. > 180.) & (dir.x > 0.))
-            {
-                dir.x *= -1.
-            }
-            vel.0 = dir;
-        }
-        vel.0 *= 1. - time.delta_seconds() * 1.0;
-        if (floater.movement_timer < 0.6) & (vel.length() != 0.) {
-            let dir = vel.normalize();
-            vel.0 += dir * time.delta_seconds() * PROPULSION;
-        }
-        floater.movement_timer += time.delta_seconds();
-        let movement = vel.0 * time.delta_seconds();
-        let movement = grid_collision(&tiles, trans.translation.xy(), FLOATER_SIZE, movement, true);
-        trans.translation += movement.extend(0.);
-        let pos = trans.translation.xy();
-        vel.0 = movement / time.delta_seconds();
-        for other in &transform {
-            let other = other.translation.xy();
-            if other != pos {
-                let distance = other.distance(pos);
-                let direction = (pos - other).normalize();
-                vel.0 += direction * (2. * FLOATER_SIZE - distance).max(0.) / 2.4;
-            }
-        }
-
-        if player_pos.translation.xy().distance(pos) < FLOATER_SIZE + PLAYER_SIZE {
-            commands.trigger(HurtPlayer);
-        }
-
-        if floater.movement_timer > 1.5 {
-            floater.movement_timer = 0.
-        }
-
-        if enemy.health <= 0. {
-            commands.entity(entity).despawn_recursive();
-            commands.spawn(AudioBundle {
-                source: handles.sfx_enemy_death.clone(),
-                settings: PlaybackSettings {
-                    mode: bevy::audio::PlaybackMode::Despawn,
-                    volume: bevy::audio::Volume::new(0.4),
-                    ..default()
+                Hurtable {
+                    last_hit: f32::INFINITY,
+                    indicator: spawner.summon_occluder,
+                    tint: affixes.tint(),
                 },
-            });
-        }
-    }
-}
-
-pub fn summoner(
-    mut commands: Commands,
-    mut summoners: Query<(Entity, &mut Vel, &mut Summoner, &Enemy)>,
-    mut transform: Query<&mut Transform, (With<Enemy>, Without<PlayerEntity>)>,
-    player: Query<&Transform, With<PlayerEntity>>,
-    tiles: Res<Tiles>,
-    time: Res<Time>,
-    handles: Res<Handles>,
-    state: Res<State<RoomState>>,
-) {
-    const PROPULSION: f32 = 70.;
-    let player_pos = player.single();
-    for (entity, mut vel, mut summoner, enemy) in &mut summoners {
-        let mut trans = transform.get_mut(entity).unwrap();
-
-        summoner.summon_timer -= time.delta_seconds();
-        if (summoner.summon_timer < 0.) & (*state == RoomState::Fighting) {
-            commands.spawn((
-                Spawner::create(trans.translation.xy(), EnemyKind::B, 0.),
-                Clearable,
+                Animation::new(archetype.animation.clone(), true),
             ));
-            summoner.summon_timer = 6.
-        }
-
-        if summoner.movement_timer == 0. {
-            let mut dir = Dir2::from_rng(&mut thread_rng()).as_vec2();
-            if ((trans.translation.y + dir.y * 24. < 10.) & (dir.y < 0.))
-                | ((trans.translation.y + dir.y * 24. > 180.) & (dir.y > 0.))
-            {
-                dir.y *= -1.
+            if !archetype.drops.is_empty() {
+                enemy.insert(Drops(archetype.drops.clone()));
             }
-            if ((trans.translation.x + dir.x * 24. < 10.) & (dir.x < 0.))
-                | ((trans.translation.x + dir.x * 24. > 180.) & (dir.x > 0.))
-            {
-                dir.x *= -1.
-            }
-            vel.0 = dir;
-        }
-        vel.0 *= 1. - time.delta_seconds() * 1.0;
-        if (summoner.movement_timer < 0.6) & (vel.length() != 0.) {
-            let dir = vel.normalize();
-            vel.0 += dir * time.delta_seconds() * PROPULSION;
-        }
-        summoner.movement_timer += time.delta_seconds();
-        let movement = vel.0 * time.delta_seconds();
-        let movement = grid_collision(&tiles, trans.translation.xy(), FLOATER_SIZE, movement, true);
-        trans.translation += movement.extend(0.);
-        let pos = trans.translation.xy();
-        vel.0 = movement / time.delta_seconds();
-        for other in &transform {
-            let other = other.translation.xy();
-            if other != pos {
-                let distance = other.distance(pos);
-                let direction = (pos - other).normalize();
-                vel.0 += direction * (2. * FLOATER_SIZE - distance).max(0.) / 2.4;
-            }
-        }
-
-        if player_pos.translation.xy().distance(pos) < FLOATER_SIZE + PLAYER_SIZE {
-            commands.trigger(HurtPlayer);
+            archetype.insert_behaviours(&mut enemy);
+            affixes.insert(&mut enemy, &spawner.kind);
         }
+    }
+}
 
-        if summoner.movement_timer > 1.5 {
-            summoner.movement_timer = 0.
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        if enemy.health <= 0. {
-            commands.entity(entity).despawn_recursive();
-            commands.spawn(AudioBundle {
-                source: handles.sfx_enemy_death.clone(),
-                settings: PlaybackSettings {
-                    mode: bevy::audio::PlaybackMode::Despawn,
-                    volume: bevy::audio::Volume::new(0.4),
-                    ..default()
-                },
-            });
-        }
+    #[test]
+    fn wave_pauses_follow_cleared_waves() {
+        let mut waves = Waves {
+            waves: vec![Vec::new(); 3],
+            pauses: vec![2.],
+            ..default()
+        };
+        let pauses = (0..3).map(|next| {
+            waves.next = next;
+            waves.pause()
+        });
+        assert_eq!(pauses.collect::<Vec<_>>(), [0., 2., 1.]);
     }
 }
-
-// fn enemies(
-//     mut enemies: Query<(Entity, &mut Vel), With<Enemy>>,
-//     trans: Query<(&Transform, Enemy)>
-// ) {
-//     for (entity, mut vel) in &mut enemies {
-//         let pos = trans.get(entity).unwrap().translation.xy();
-//         for (other_pos, other) in &transform {
-//             let other = other.translation.xy();
-//             if other != pos {
-//                 let distance = other.distance(pos);
-//                 let direction = (pos - other).normalize();
-//                 vel.0 += direction * (2. * FLOATER_SIZE - distance).max(0.) / 2.4;
-//             }
-//         }
-//     }
-// }
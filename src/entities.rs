@@ -0,0 +1,65 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    enemy::spawn_enemy_spawn_point,
+    ldtk::{EntityInstance, FieldValue, Fields},
+    level::{spawn_door, spawn_gargoyle},
+    player::spawn_player,
+    Cycle, Handles,
+};
+
+pub struct SpawnContext<'a> {
+    pub handles: &'a Handles,
+    pub cycle: &'a Cycle,
+    /// Position of the entity's pivot in world space
+    pub pos: Vec2,
+}
+
+/// Reads a field designers may leave out, warning about malformed values
+pub fn field_or_warn<T: FieldValue + Clone>(
+    entity: &EntityInstance,
+    identifier: &str,
+    default: T,
+) -> T {
+    entity
+        .field_or(identifier, default.clone())
+        .unwrap_or_else(|err| {
+            warn!("{} {}: {err}", entity.identifier, entity.iid);
+            default
+        })
+}
+
+pub type SpawnEntity = fn(&mut Commands, &EntityInstance, &SpawnContext);
+
+/// Maps LDtk entity identifiers to the functions spawning them
+#[derive(Resource)]
+pub struct EntityRegistry(HashMap<String, SpawnEntity>);
+
+impl EntityRegistry {
+    pub fn register(&mut self, identifier: &str, spawn: SpawnEntity) -> &mut Self {
+        self.0.insert(identifier.to_owned(), spawn);
+        self
+    }
+
+    pub fn spawn(&self, commands: &mut Commands, entity: &EntityInstance, ctx: &SpawnContext) {
+        match self.0.get(&entity.identifier) {
+            Some(spawn) => spawn(commands, entity, ctx),
+            None => warn!(
+                "No spawn function registered for entity {}",
+                entity.identifier
+            ),
+        }
+    }
+}
+
+impl Default for EntityRegistry {
+    fn default() -> Self {
+        let mut registry = Self(HashMap::new());
+        registry
+            .register("Door", spawn_door)
+            .register("EnemySpawn", spawn_enemy_spawn_point)
+            .register("Gargoyle", spawn_gargoyle)
+            .register("Player", spawn_player);
+        registry
+    }
+}
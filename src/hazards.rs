@@ -0,0 +1,82 @@
+use bevy::{prelude::*, sprite::Anchor, utils::HashMap};
+
+use crate::{
+    level::{Tile, TileChanged, Tiles, CELL_SIZE},
+    player::{HurtPlayer, PlayerEntity, PLAYER_SIZE},
+    Clearable, Handles, Layer,
+};
+
+const CRUMBLE_TIME: f32 = 0.8;
+/// Fraction of its usual speed anything walking through goo moves at
+pub const GOO_SLOWDOWN: f32 = 12. / 28.;
+/// Speed conveyors carry anything walking on them at, without adding to its momentum
+pub const CONVEYOR_SPEED: f32 = 20.;
+
+/// Crumbling floor the player has stepped on, with the time since and its darkening overlay
+#[derive(Resource, Default)]
+pub struct Crumbling(HashMap<IVec2, (f32, Entity)>);
+
+pub fn reset_hazards(mut commands: Commands) {
+    commands.insert_resource(Crumbling::default());
+}
+
+pub fn hazards(
+    mut commands: Commands,
+    mut tiles: ResMut<Tiles>,
+    mut crumbling: ResMut<Crumbling>,
+    player: Query<&Transform, With<PlayerEntity>>,
+    mut overlays: Query<&mut Sprite>,
+    handles: Res<Handles>,
+    time: Res<Time>,
+) {
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    let pos = player.translation.xy();
+    let cell = Tiles::cell_at(pos);
+    match tiles.at(pos) {
+        Some(Tile::Spikes) => commands.trigger(HurtPlayer {
+            source: None,
+            unavoidable: false,
+        }),
+        Some(Tile::Crumbling) if !crumbling.0.contains_key(&cell) => {
+            let overlay = commands
+                .spawn((
+                    Clearable,
+                    Layer(-0.5),
+                    SpriteBundle {
+                        texture: handles.black.clone(),
+                        transform: Transform::from_translation(
+                            (cell.as_vec2() * CELL_SIZE).extend(0.),
+                        ),
+                        sprite: Sprite {
+                            color: Color::srgba(0., 0., 0., 0.),
+                            anchor: Anchor::BottomLeft,
+                            ..default()
+                        },
+                        ..default()
+                    },
+                ))
+                .id();
+            crumbling.0.insert(cell, (0., overlay));
+        }
+        _ => {}
+    }
+
+    crumbling.0.retain(|&crumbling_cell, (timer, overlay)| {
+        *timer += time.delta_seconds();
+        let progress = (*timer / CRUMBLE_TIME).min(1.);
+        // Only collapse once the player no longer touches the tile
+        let min = crumbling_cell.as_vec2() * CELL_SIZE;
+        let touching = pos.clamp(min, min + CELL_SIZE).distance(pos) < PLAYER_SIZE;
+        let collapse = (progress >= 1.) & !touching;
+        if collapse {
+            tiles[crumbling_cell] = Tile::Pit;
+            commands.trigger(TileChanged(crumbling_cell));
+            commands.entity(*overlay).despawn();
+        } else if let Ok(mut sprite) = overlays.get_mut(*overlay) {
+            sprite.color = Color::srgba(0., 0., 0., progress * 0.6);
+        }
+        !collapse
+    });
+}
@@ -0,0 +1,32 @@
+use bevy::prelude::*;
+
+/// Briefly freezes the game for this many seconds, to give a hit more impact
+#[derive(Event)]
+pub struct Hitstop(pub f32);
+
+/// Real time left until the game resumes
+#[derive(Resource, Default)]
+pub struct HitstopTimer(f32);
+
+pub fn start_hitstop(
+    trigger: Trigger<Hitstop>,
+    mut timer: ResMut<HitstopTimer>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    timer.0 = timer.0.max(trigger.event().0);
+    time.pause();
+}
+
+pub fn hitstop(
+    mut timer: ResMut<HitstopTimer>,
+    mut time: ResMut<Time<Virtual>>,
+    real: Res<Time<Real>>,
+) {
+    if timer.0 <= 0. {
+        return;
+    }
+    timer.0 -= real.delta_seconds();
+    if timer.0 <= 0. {
+        time.unpause();
+    }
+}
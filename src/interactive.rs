@@ -0,0 +1,67 @@
+use bevy::{math::ivec2, prelude::*};
+
+use crate::{
+    camera::Shake,
+    level::{Tile, TileChanged, Tiles},
+    player::PlayerEntity,
+    Handles,
+};
+
+/// Replaces every tile of one kind, e.g. opening all gates
+fn replace_all(commands: &mut Commands, tiles: &mut Tiles, from: Tile, to: Tile) {
+    for x in 0..tiles.size.x {
+        for y in 0..tiles.size.y {
+            let cell = ivec2(x, y);
+            if tiles[cell] == from {
+                tiles[cell] = to;
+                commands.trigger(TileChanged(cell));
+            }
+        }
+    }
+}
+
+/// Called when a friendly bullet hits a blocking tile
+pub fn hit_tile(commands: &mut Commands, tiles: &mut Tiles, cell: IVec2) {
+    if !tiles.contains(cell) {
+        return;
+    }
+    if let Tile::Cracked(hits) = tiles[cell] {
+        tiles[cell] = if hits > 1 {
+            Tile::Cracked(hits - 1)
+        } else {
+            commands.trigger(Shake(0.3));
+            Tile::Floor
+        };
+        commands.trigger(TileChanged(cell));
+    }
+}
+
+pub fn press_switches(
+    mut commands: Commands,
+    mut tiles: ResMut<Tiles>,
+    player: Query<&Transform, With<PlayerEntity>>,
+    handles: Res<Handles>,
+) {
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    let cell = Tiles::cell_at(player.translation.xy());
+    if tiles.at(player.translation.xy()) != Some(Tile::Switch { pressed: false }) {
+        return;
+    }
+    tiles[cell] = Tile::Switch { pressed: true };
+    commands.trigger(TileChanged(cell));
+    replace_all(&mut commands, &mut tiles, Tile::Gate, Tile::Floor);
+    commands.spawn(AudioBundle {
+        source: handles.sfx_summon.clone(),
+        settings: PlaybackSettings {
+            mode: bevy::audio::PlaybackMode::Despawn,
+            volume: bevy::audio::Volume::new(0.4),
+            ..default()
+        },
+    });
+}
+
+pub fn drop_barriers(mut commands: Commands, mut tiles: ResMut<Tiles>) {
+    replace_all(&mut commands, &mut tiles, Tile::Barrier, Tile::Floor);
+}
@@ -2,6 +2,7 @@
 
 use bevy::{
     asset::{io::Reader, Asset, AssetLoader, AsyncReadExt, LoadContext},
+    color::{Color, Srgba},
     math::IVec2,
     prelude::Resource,
     reflect::TypePath,
@@ -1356,3 +1357,330 @@ pub enum ImageExportMode {
     #[serde(rename = "OneImagePerLevel")]
     OneImagePerLevel,
 }
+
+#[derive(Debug)]
+pub enum FieldError {
+    Missing {
+        field: String,
+    },
+    Null {
+        field: String,
+    },
+    WrongType {
+        field: String,
+        expected: &'static str,
+        found: String,
+    },
+}
+
+impl std::fmt::Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldError::Missing { field } => write!(f, "field {field} does not exist"),
+            FieldError::Null { field } => write!(f, "field {field} has no value"),
+            FieldError::WrongType {
+                field,
+                expected,
+                found,
+            } => write!(f, "field {field} is of type {found}, expected {expected}"),
+        }
+    }
+}
+
+impl std::error::Error for FieldError {}
+
+/// A type that field instance values can be read as
+pub trait FieldValue: Sized {
+    /// LDtk type name, used in errors
+    const TYPE: &'static str;
+
+    fn matches_type(field_type: &str) -> bool;
+
+    fn from_json(value: &serde_json::Value) -> Option<Self>;
+}
+
+impl FieldValue for i32 {
+    const TYPE: &'static str = "Int";
+
+    fn matches_type(field_type: &str) -> bool {
+        field_type == "Int"
+    }
+
+    fn from_json(value: &serde_json::Value) -> Option<Self> {
+        value.as_i64().map(|v| v as i32)
+    }
+}
+
+impl FieldValue for f32 {
+    const TYPE: &'static str = "Float";
+
+    fn matches_type(field_type: &str) -> bool {
+        matches!(field_type, "Float" | "Int")
+    }
+
+    fn from_json(value: &serde_json::Value) -> Option<Self> {
+        value.as_f64().map(|v| v as f32)
+    }
+}
+
+impl FieldValue for bool {
+    const TYPE: &'static str = "Bool";
+
+    fn matches_type(field_type: &str) -> bool {
+        field_type == "Bool"
+    }
+
+    fn from_json(value: &serde_json::Value) -> Option<Self> {
+        value.as_bool()
+    }
+}
+
+impl FieldValue for String {
+    const TYPE: &'static str = "String";
+
+    fn matches_type(field_type: &str) -> bool {
+        matches!(field_type, "String" | "Multilines" | "FilePath")
+    }
+
+    fn from_json(value: &serde_json::Value) -> Option<Self> {
+        value.as_str().map(str::to_owned)
+    }
+}
+
+/// The selected value of an enum field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnumValue(pub String);
+
+impl FieldValue for EnumValue {
+    const TYPE: &'static str = "Enum";
+
+    fn matches_type(field_type: &str) -> bool {
+        field_type.starts_with("LocalEnum.")
+            | field_type.starts_with("ExternEnum.")
+            | field_type.starts_with("Enum(")
+    }
+
+    fn from_json(value: &serde_json::Value) -> Option<Self> {
+        value.as_str().map(|v| EnumValue(v.to_owned()))
+    }
+}
+
+impl FieldValue for Color {
+    const TYPE: &'static str = "Color";
+
+    fn matches_type(field_type: &str) -> bool {
+        field_type == "Color"
+    }
+
+    fn from_json(value: &serde_json::Value) -> Option<Self> {
+        Srgba::hex(value.as_str()?).ok().map(Color::Srgba)
+    }
+}
+
+/// Points are given in grid coordinates, with y pointing down
+impl FieldValue for IVec2 {
+    const TYPE: &'static str = "Point";
+
+    fn matches_type(field_type: &str) -> bool {
+        field_type == "Point"
+    }
+
+    fn from_json(value: &serde_json::Value) -> Option<Self> {
+        let point = GridPoint::deserialize(value).ok()?;
+        Some(IVec2::new(point.cx, point.cy))
+    }
+}
+
+impl FieldValue for ReferenceToAnEntityInstance {
+    const TYPE: &'static str = "EntityRef";
+
+    fn matches_type(field_type: &str) -> bool {
+        field_type == "EntityRef"
+    }
+
+    fn from_json(value: &serde_json::Value) -> Option<Self> {
+        ReferenceToAnEntityInstance::deserialize(value).ok()
+    }
+}
+
+/// For fields that may be null
+impl<T: FieldValue> FieldValue for Option<T> {
+    const TYPE: &'static str = T::TYPE;
+
+    fn matches_type(field_type: &str) -> bool {
+        T::matches_type(field_type)
+    }
+
+    fn from_json(value: &serde_json::Value) -> Option<Self> {
+        if value.is_null() {
+            Some(None)
+        } else {
+            T::from_json(value).map(Some)
+        }
+    }
+}
+
+impl<T: FieldValue> FieldValue for Vec<T> {
+    const TYPE: &'static str = "Array";
+
+    fn matches_type(field_type: &str) -> bool {
+        field_type
+            .strip_prefix("Array<")
+            .and_then(|t| t.strip_suffix('>'))
+            .is_some_and(T::matches_type)
+    }
+
+    fn from_json(value: &serde_json::Value) -> Option<Self> {
+        value.as_array()?.iter().map(T::from_json).collect()
+    }
+}
+
+impl FieldInstance {
+    pub fn get<T: FieldValue>(&self) -> Result<T, FieldError> {
+        let wrong_type = || FieldError::WrongType {
+            field: self.identifier.clone(),
+            expected: T::TYPE,
+            found: self.field_instance_type.clone(),
+        };
+        if !T::matches_type(&self.field_instance_type) {
+            return Err(wrong_type());
+        }
+        let value = self.value.as_ref().unwrap_or(&serde_json::Value::Null);
+        T::from_json(value).ok_or_else(|| {
+            if value.is_null() {
+                FieldError::Null {
+                    field: self.identifier.clone(),
+                }
+            } else {
+                wrong_type()
+            }
+        })
+    }
+}
+
+/// Typed access to custom fields of entities and levels
+pub trait Fields {
+    fn field_instances(&self) -> &[FieldInstance];
+
+    fn field<T: FieldValue>(&self, identifier: &str) -> Result<T, FieldError> {
+        self.field_instances()
+            .iter()
+            .find(|f| f.identifier == identifier)
+            .ok_or_else(|| FieldError::Missing {
+                field: identifier.to_owned(),
+            })?
+            .get()
+    }
+
+    /// Falls back to `default` if the field doesn't exist or is null
+    fn field_or<T: FieldValue>(&self, identifier: &str, default: T) -> Result<T, FieldError> {
+        match self.field(identifier) {
+            Err(FieldError::Missing { .. } | FieldError::Null { .. }) => Ok(default),
+            result => result,
+        }
+    }
+}
+
+impl Fields for EntityInstance {
+    fn field_instances(&self) -> &[FieldInstance] {
+        &self.field_instances
+    }
+}
+
+impl Fields for Level {
+    fn field_instances(&self) -> &[FieldInstance] {
+        &self.field_instances
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    struct TestFields(Vec<FieldInstance>);
+
+    impl Fields for TestFields {
+        fn field_instances(&self) -> &[FieldInstance] {
+            &self.0
+        }
+    }
+
+    fn field(identifier: &str, field_type: &str, value: serde_json::Value) -> FieldInstance {
+        FieldInstance {
+            identifier: identifier.to_owned(),
+            tile: None,
+            field_instance_type: field_type.to_owned(),
+            value: Some(value),
+            def_uid: 0,
+            real_editor_values: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn reads_values() {
+        let fields = TestFields(vec![
+            field("Int", "Int", json!(3)),
+            field("Float", "Float", json!(0.5)),
+            field("Bool", "Bool", json!(true)),
+            field("String", "String", json!("hello")),
+            field("Enum", "LocalEnum.Kind", json!("Walker")),
+            field("Color", "Color", json!("#ff0000")),
+            field("Point", "Point", json!({ "cx": 2, "cy": 5 })),
+            field("Array", "Array<Float>", json!([1, 2.5])),
+        ]);
+        assert_eq!(fields.field::<i32>("Int").unwrap(), 3);
+        assert_eq!(fields.field::<f32>("Float").unwrap(), 0.5);
+        assert!(fields.field::<bool>("Bool").unwrap());
+        assert_eq!(fields.field::<String>("String").unwrap(), "hello");
+        assert_eq!(
+            fields.field::<EnumValue>("Enum").unwrap(),
+            EnumValue("Walker".to_owned())
+        );
+        assert_eq!(
+            fields.field::<Color>("Color").unwrap(),
+            Color::srgb(1., 0., 0.)
+        );
+        assert_eq!(fields.field::<IVec2>("Point").unwrap(), IVec2::new(2, 5));
+        assert_eq!(fields.field::<Vec<f32>>("Array").unwrap(), vec![1., 2.5]);
+    }
+
+    #[test]
+    fn ints_read_as_floats() {
+        let fields = TestFields(vec![field("Int", "Int", json!(4))]);
+        assert_eq!(fields.field::<f32>("Int").unwrap(), 4.);
+    }
+
+    #[test]
+    fn reports_errors() {
+        let fields = TestFields(vec![
+            field("Null", "Int", serde_json::Value::Null),
+            field("Float", "Float", json!(1.5)),
+        ]);
+        assert!(matches!(
+            fields.field::<i32>("Nope"),
+            Err(FieldError::Missing { field }) if field == "Nope"
+        ));
+        assert!(matches!(
+            fields.field::<i32>("Null"),
+            Err(FieldError::Null { .. })
+        ));
+        assert!(matches!(
+            fields.field::<i32>("Float"),
+            Err(FieldError::WrongType { expected: "Int", found, .. }) if found == "Float"
+        ));
+        assert_eq!(fields.field::<Option<i32>>("Null").unwrap(), None);
+    }
+
+    #[test]
+    fn falls_back_to_default() {
+        let fields = TestFields(vec![
+            field("Null", "Int", serde_json::Value::Null),
+            field("Float", "Float", json!(1.5)),
+        ]);
+        assert_eq!(fields.field_or("Nope", 7).unwrap(), 7);
+        assert_eq!(fields.field_or("Null", 7).unwrap(), 7);
+        assert_eq!(fields.field_or("Float", 0.).unwrap(), 1.5);
+        assert!(fields.field_or("Float", 7).is_err());
+    }
+}
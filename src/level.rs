@@ -1,80 +1,222 @@
 use std::f32::consts::PI;
 
 use bevy::{
-    math::{vec2, vec3},
+    math::{ivec2, vec2, vec3},
     prelude::*,
     sprite::Anchor,
-    utils::HashMap,
+    utils::{HashMap, HashSet},
 };
 
 use crate::{
-    bullet::Bullet,
-    ldtk::{EntityInstance, LdtkProject},
-    player::{PlayerEntity, PlayerHurtFlash},
-    Clearable, Cycle, Door, Gargoyle, Handles, Layer, Vel,
+    camera::RoomEntered,
+    entities::{field_or_warn, EntityRegistry, SpawnContext},
+    ldtk::{EntityInstance, EnumValue, Fields, LdtkProject, Level},
+    pattern::{Aim, Emitter, Pattern, Shape},
+    pit::PitFalling,
+    telegraph::Telegraph,
+    tilemap::{PlacedTile, TileOverlays, Tilemap, ZLayer},
+    Clearable, Cycle, Destination, Door, Handles, Layer, RoomKind,
 };
 
 pub static CELL_SIZE: f32 = 12.;
-static LEVEL_WIDTH: i32 = 16 * CELL_SIZE as i32;
-static LEVEL_HEIGHT: i32 = 16 * CELL_SIZE as i32;
+// Rooms are laid out on this grid in the LDtk world, but may span several cells
+pub static WORLD_GRID_WIDTH: i32 = 16 * CELL_SIZE as i32;
+pub static WORLD_GRID_HEIGHT: i32 = 16 * CELL_SIZE as i32;
 
-#[derive(Clone, Copy)]
-enum ZLayer {
-    Subfloor,
-    Floor,
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum Tile {
     Wall,
-    Top,
+    Floor,
+    Pit,
+    /// Hurts the player while standing on it
+    Spikes,
+    /// Slows down walking
+    Goo,
+    /// Pushes anything walking on it in the given direction
+    Conveyor(IVec2),
+    /// Turns into a pit shortly after the player steps off it
+    Crumbling,
+    /// Wall that breaks after the given number of hits by the player
+    Cracked(u8),
+    /// Opens all gates once stepped on
+    Switch {
+        pressed: bool,
+    },
+    Gate,
+    /// Blocks the way until the room is cleared
+    Barrier,
 }
 
-impl ZLayer {
-    fn base_z(self) -> f32 {
+impl Tile {
+    /// Maps the identifier of an IntGrid value in LDtk to a tile
+    fn from_identifier(identifier: &str) -> Option<Self> {
+        Some(match identifier {
+            "floor" => Tile::Floor,
+            "pit" => Tile::Pit,
+            "oob" => Tile::Wall,
+            "spikes" => Tile::Spikes,
+            "goo" => Tile::Goo,
+            "conveyor_up" => Tile::Conveyor(IVec2::Y),
+            "conveyor_down" => Tile::Conveyor(IVec2::NEG_Y),
+            "conveyor_left" => Tile::Conveyor(IVec2::NEG_X),
+            "conveyor_right" => Tile::Conveyor(IVec2::X),
+            "crumbling" => Tile::Crumbling,
+            "cracked_wall" => Tile::Cracked(3),
+            "switch" => Tile::Switch { pressed: false },
+            "gate" => Tile::Gate,
+            "barrier" => Tile::Barrier,
+            _ => return None,
+        })
+    }
+
+    pub fn blocks(self, flying: bool) -> bool {
         match self {
-            ZLayer::Subfloor => -2.,
-            ZLayer::Floor => -1.,
-            ZLayer::Wall => 0.,
-            ZLayer::Top => 1.,
+            Tile::Wall | Tile::Cracked(_) | Tile::Gate | Tile::Barrier => true,
+            Tile::Pit => !flying,
+            _ => false,
         }
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, PartialOrd)]
-pub enum Tile {
-    Wall,
-    Floor,
-    Pit,
-}
-
 #[derive(Resource)]
 pub struct Tiles {
+    pub size: IVec2,
     pub grid: Vec<Tile>,
 }
 
+impl Tiles {
+    pub fn contains(&self, pos: IVec2) -> bool {
+        (0..self.size.x).contains(&pos.x) & (0..self.size.y).contains(&pos.y)
+    }
+
+    /// Size of the room in world units
+    pub fn px_size(&self) -> Vec2 {
+        self.size.as_vec2() * CELL_SIZE
+    }
+
+    pub fn cell_at(pos: Vec2) -> IVec2 {
+        (pos / CELL_SIZE).floor().as_ivec2()
+    }
+
+    /// Tile at a world position, if inside the room
+    pub fn at(&self, pos: Vec2) -> Option<Tile> {
+        let cell = Self::cell_at(pos);
+        self.contains(cell).then(|| self[cell])
+    }
+
+    /// Walks the cells crossed by the segment from `from` to `to` (DDA) and returns the first
+    /// one that blocks, with the point where the segment enters it. Leaving the room counts as
+    /// hitting a wall.
+    pub fn raycast(&self, from: Vec2, to: Vec2, flying: bool) -> Option<(IVec2, Vec2)> {
+        let start = from / CELL_SIZE;
+        let delta = (to - from) / CELL_SIZE;
+        let mut cell = start.floor().as_ivec2();
+        let end = (to / CELL_SIZE).floor().as_ivec2();
+        let step = ivec2(delta.x.signum() as i32, delta.y.signum() as i32);
+        let t_delta = delta.recip().abs();
+        let boundary = |start: f32, cell: i32, delta: f32, t_delta: f32| {
+            if delta > 0. {
+                (cell as f32 + 1. - start) * t_delta
+            } else if delta < 0. {
+                (start - cell as f32) * t_delta
+            } else {
+                f32::INFINITY
+            }
+        };
+        let mut t_max = vec2(
+            boundary(start.x, cell.x, delta.x, t_delta.x),
+            boundary(start.y, cell.y, delta.y, t_delta.y),
+        );
+        let mut t = 0.;
+        loop {
+            if !self.contains(cell) || self[cell].blocks(flying) {
+                return Some((cell, from + (to - from) * t));
+            }
+            if cell == end {
+                return None;
+            }
+            if t_max.x < t_max.y {
+                cell.x += step.x;
+                t = t_max.x;
+                t_max.x += t_delta.x;
+            } else {
+                cell.y += step.y;
+                t = t_max.y;
+                t_max.y += t_delta.y;
+            }
+            if t > 1. {
+                return None;
+            }
+        }
+    }
+
+    /// Whether nothing blocks sight between two points. Pits can be seen across.
+    pub fn line_of_sight(&self, from: Vec2, to: Vec2) -> bool {
+        self.raycast(from, to, true).is_none()
+    }
+
+    /// Whether a cell is hidden behind the tall wall tiles drawn in front of it
+    pub fn behind_wall(&self, cell: IVec2) -> bool {
+        (1..=2).any(|dy| {
+            let below = cell - IVec2::Y * dy;
+            self.contains(below) && self[below].blocks(true)
+        })
+    }
+
+    fn index_of(&self, pos: IVec2) -> usize {
+        (pos.x + (self.size.y - 1 - pos.y) * self.size.x) as usize
+    }
+}
+
 impl std::ops::Index<IVec2> for Tiles {
     type Output = Tile;
 
     fn index(&self, index: IVec2) -> &Self::Output {
-        &self.grid[(index.x + (15 - index.y) * 16) as usize]
+        &self.grid[self.index_of(index)]
     }
 }
 
+impl std::ops::IndexMut<IVec2> for Tiles {
+    fn index_mut(&mut self, index: IVec2) -> &mut Self::Output {
+        let index = self.index_of(index);
+        &mut self.grid[index]
+    }
+}
+
+/// Triggered after changing a tile during a room, so that it gets redrawn
+#[derive(Event)]
+pub struct TileChanged(pub IVec2);
+
 #[derive(Default, Component)]
 pub struct DeleteOnClear;
 
+pub fn current_level<'a>(ldtk: &'a LdtkProject, cycle: &Cycle) -> &'a Level {
+    if let Some(detour) = cycle.detour {
+        let iid = &cycle.side_rooms[detour].level_iid;
+        return ldtk.levels.iter().find(|level| &level.iid == iid).unwrap();
+    }
+    let room = &cycle.rooms[cycle.current_room];
+    ldtk.levels
+        .iter()
+        .find(|level| {
+            (level.world_x == room.id * WORLD_GRID_WIDTH)
+                && (level.world_y == room.difficulty * WORLD_GRID_HEIGHT)
+        })
+        .unwrap()
+}
+
 pub fn spawn_level(
     mut commands: Commands,
     ldtk: Res<LdtkProject>,
     handles: Res<Handles>,
     cycle: Res<Cycle>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    images: Res<Assets<Image>>,
+    registry: Res<EntityRegistry>,
 ) {
-    let cycle_progress = cycle.current_room as f32 / cycle.rooms.len() as f32 + 0.1;
-    let ldtk_level = ldtk
-        .levels
-        .iter()
-        .find(|level| {
-            (level.world_x == cycle.rooms[cycle.current_room].id * LEVEL_WIDTH)
-                && (level.world_y == cycle.rooms[cycle.current_room].difficulty * LEVEL_HEIGHT)
-        })
-        .unwrap();
+    let ldtk_level = current_level(&ldtk, &cycle);
+
+    let level_height = ldtk_level.px_hei as f32;
 
     // Collision data
     let tile_layer = ldtk_level
@@ -85,19 +227,53 @@ pub fn spawn_level(
         .find(|l| l.identifier == "Tiles")
         .unwrap();
 
-    commands.insert_resource(Tiles {
+    let values: HashMap<i32, Tile> = ldtk
+        .defs
+        .layers
+        .iter()
+        .find(|l| l.uid == tile_layer.layer_def_uid)
+        .unwrap()
+        .int_grid_values
+        .iter()
+        .filter_map(|value| {
+            let identifier = value.identifier.as_deref().unwrap_or_default();
+            let tile = Tile::from_identifier(identifier);
+            if tile.is_none() {
+                warn!("Unknown IntGrid value {} \"{identifier}\"", value.value);
+            }
+            Some((value.value, tile?))
+        })
+        .collect();
+
+    let size = ivec2(tile_layer.c_width, tile_layer.c_height);
+    let tiles = Tiles {
+        size,
         grid: tile_layer
             .int_grid_csv
             .iter()
-            .map(|t| match t {
-                1 => Tile::Floor,
-                2 => Tile::Pit,
-                _ => Tile::Wall,
-            })
+            .map(|t| values.get(t).copied().unwrap_or(Tile::Wall))
             .collect(),
-    });
+    };
+    // Tiles without tiles in the tileset, drawn once the tilemap exists
+    let mut special = Vec::new();
+    for x in 0..size.x {
+        for y in 0..size.y {
+            if !matches!(tiles[ivec2(x, y)], Tile::Floor | Tile::Wall | Tile::Pit) {
+                special.push(ivec2(x, y));
+            }
+        }
+    }
+    commands.insert_resource(tiles);
+    commands.trigger(RoomEntered);
+
+    let pit_falling = ldtk_level
+        .field_or("PitFalling", true)
+        .unwrap_or_else(|err| {
+            warn!("{}: {err}", ldtk_level.identifier);
+            true
+        });
+    commands.insert_resource(PitFalling(pit_falling));
 
-    assert_eq!((tile_layer.c_width, tile_layer.c_height), (16, 16));
     assert_eq!(
         (tile_layer.px_total_offset_x, tile_layer.px_total_offset_y),
         (0, 0)
@@ -110,8 +286,9 @@ pub fn spawn_level(
         .iter()
         .find(|t| t.identifier == "Tiles")
         .unwrap();
-    let mut z_layers = [ZLayer::Floor; 100];
-    let mut transparent = [false; 100];
+    let tile_count = (tileset.c_wid * tileset.c_hei) as usize;
+    let mut z_layers = vec![ZLayer::Floor; tile_count];
+    let mut transparent = vec![false; tile_count];
     for value in &tileset.enum_tags {
         match value.enum_value_id.as_str() {
             "Subfloor" => {
@@ -143,50 +320,54 @@ pub fn spawn_level(
         }
     }
 
-    let auto_layer = ldtk_level
-        .layer_instances
-        .as_ref()
-        .unwrap()
-        .iter()
-        .find(|l| l.identifier == "AutoLayer")
-        .unwrap();
+    // Layers are listed topmost first, and so are the tiles we want to keep
+    let mut layer_tiles = Vec::new();
+    for layer in ldtk_level.layer_instances.as_ref().unwrap() {
+        if !layer.visible | layer.tileset_def_uid.is_none() {
+            continue;
+        }
+        if layer.tileset_def_uid != Some(tileset.uid) {
+            warn!(
+                "Layer {} of {} uses an unsupported tileset",
+                layer.identifier, ldtk_level.identifier
+            );
+            continue;
+        }
+        let offset = ivec2(layer.px_total_offset_x, layer.px_total_offset_y);
+        for tile in layer.grid_tiles.iter().chain(&layer.auto_layer_tiles).rev() {
+            layer_tiles.push((tile, offset, layer.opacity));
+        }
+    }
 
-    let mut counts = HashMap::new();
-    let mut ids = HashMap::new();
-    for tile in auto_layer.auto_layer_tiles.iter().rev() {
-        let pos = vec2(tile.px.x as f32, CELL_SIZE * 15. - tile.px.y as f32);
-        let count = counts.entry(pos.as_ivec2()).or_insert(0);
-        *count += 1;
-        let z = z_layers[tile.t as usize].base_z() - *count as f32 / 10000.;
-        if let Some(&id) = ids.get(&pos.as_ivec2()) {
-            if !transparent[id as usize] {
-                continue;
-            }
+    let mut covered = HashSet::new();
+    let mut visible = Vec::new();
+    for (tile, offset, opacity) in layer_tiles {
+        let px = tile.px + offset;
+        let pos = vec2(px.x as f32, level_height - CELL_SIZE - px.y as f32);
+        if covered.contains(&px) {
+            continue;
+        }
+        let alpha = tile.a * opacity;
+        if !transparent[tile.t as usize] & (alpha >= 1.) {
+            covered.insert(px);
         }
-        ids.insert(pos.as_ivec2(), tile.t);
+        visible.push(PlacedTile {
+            pos,
+            src: tile.src,
+            flip_x: tile.f & 1 != 0,
+            flip_y: tile.f & 2 != 0,
+            alpha,
+            z_layer: z_layers[tile.t as usize],
+        });
+    }
+    visible.reverse();
 
-        commands.spawn((
-            Clearable,
-            Layer(z),
-            SpriteBundle {
-                sprite: Sprite {
-                    anchor: Anchor::BottomLeft,
-                    ..default()
-                },
-                transform: Transform {
-                    translation: vec3(pos.x, pos.y, 0.),
-                    // Workaround gaps from not being pixel-perfect
-                    scale: vec3(1.01, 1.01, 1.),
-                    ..default()
-                },
-                texture: handles.tiles.clone(),
-                ..default()
-            },
-            TextureAtlas {
-                layout: handles.layout.clone(),
-                index: tile.t as usize,
-            },
-        ));
+    let material = materials.add(ColorMaterial::from(handles.tiles.clone()));
+    let tileset_size = images.get(&handles.tiles).unwrap().size().as_vec2();
+    commands.insert_resource(Tilemap::new(material, tileset_size, visible));
+    commands.insert_resource(TileOverlays::default());
+    for cell in special {
+        commands.trigger(TileChanged(cell));
     }
 
     // Markers
@@ -198,119 +379,117 @@ pub fn spawn_level(
         .find(|l| l.identifier == "Entities")
         .unwrap();
 
-    let px_to_world = |entity: &EntityInstance| vec2(entity.px.x as f32, 192. - entity.px.y as f32);
-    for entity in entity_layer
-        .entity_instances
-        .iter()
-        .filter(|e| e.identifier == "Door")
-    {
-        commands
-            .spawn((
-                Door,
-                Clearable,
-                Layer(0.),
-                SpriteBundle {
-                    transform: Transform::from_translation(
-                        px_to_world(entity).extend(0.) - Vec3::Y * 12.,
-                    ),
-                    texture: handles.door.clone(),
-                    sprite: Sprite {
-                        anchor: Anchor::BottomCenter,
-                        ..default()
-                    },
-                    ..default()
-                },
-            ))
-            .with_children(|b| {
-                b.spawn((
-                    DeleteOnClear,
-                    SpriteBundle {
-                        transform: Transform::from_xyz(0., 0., 0.001),
-                        texture: handles.grate.clone(),
-                        sprite: Sprite {
-                            anchor: Anchor::BottomCenter,
-                            ..default()
-                        },
-                        ..default()
-                    },
-                ));
-                b.spawn((
-                    DeleteOnClear,
-                    SpriteBundle {
-                        transform: Transform {
-                            translation: vec3(0., 8., 0.0005),
-                            rotation: Quat::from_rotation_z(cycle_progress * PI * -2.),
-                            ..default()
-                        },
-                        texture: handles.cycle_indicator.clone(),
-                        ..default()
-                    },
-                ));
-            });
+    let offset = ivec2(
+        entity_layer.px_total_offset_x,
+        entity_layer.px_total_offset_y,
+    );
+    for entity in &entity_layer.entity_instances {
+        let px = entity.px + offset;
+        let ctx = SpawnContext {
+            handles: &handles,
+            cycle: &cycle,
+            pos: vec2(px.x as f32, level_height - px.y as f32),
+        };
+        registry.spawn(&mut commands, entity, &ctx);
     }
+}
 
-    for entity in entity_layer
-        .entity_instances
-        .iter()
-        .filter(|e| e.identifier == "Gargoyle")
-    {
-        commands.spawn((
-            Gargoyle,
+pub fn spawn_door(commands: &mut Commands, entity: &EntityInstance, ctx: &SpawnContext) {
+    let cycle_progress = ctx.cycle.current_room as f32 / ctx.cycle.rooms.len() as f32 + 0.1;
+    let destination = field_or_warn(entity, "Destination", EnumValue("Next".to_owned()));
+    let (destination, color) = match destination.0.as_str() {
+        "Next" => (Destination::Next, Color::WHITE),
+        "Harder" => (Destination::Harder, Color::srgb(1., 0.6, 0.6)),
+        "Rest" => (Destination::Side(RoomKind::Rest), Color::srgb(0.7, 1., 0.7)),
+        "Secret" => (
+            Destination::Side(RoomKind::Secret),
+            Color::srgb(0.9, 0.7, 1.),
+        ),
+        other => {
+            warn!("Unknown door destination {other}");
+            (Destination::Next, Color::WHITE)
+        }
+    };
+    commands
+        .spawn((
+            Door { destination },
             Clearable,
             Layer(0.),
             SpriteBundle {
-                transform: Transform::from_translation(
-                    px_to_world(entity).extend(0.) - Vec3::Y * 4.,
-                ),
-                texture: handles.gargoyle.clone(),
+                transform: Transform::from_translation(ctx.pos.extend(0.) - Vec3::Y * 12.),
+                texture: ctx.handles.door.clone(),
                 sprite: Sprite {
                     anchor: Anchor::BottomCenter,
+                    color,
                     ..default()
                 },
                 ..default()
             },
-        ));
-    }
-
-    for entity in entity_layer
-        .entity_instances
-        .iter()
-        .filter(|e| e.identifier == "Player")
-    {
-        commands
-            .spawn((
-                PlayerEntity,
-                Layer(0.0),
-                Clearable,
-                Vel::default(),
+        ))
+        .with_children(|b| {
+            b.spawn((
+                DeleteOnClear,
                 SpriteBundle {
-                    transform: Transform::from_translation(px_to_world(entity).extend(0.)),
+                    transform: Transform::from_xyz(0., 0., 0.001),
+                    texture: ctx.handles.grate.clone(),
                     sprite: Sprite {
-                        anchor: Anchor::Custom(vec2(0., -0.5 + 3. / 18.)),
+                        anchor: Anchor::BottomCenter,
                         ..default()
                     },
-                    texture: handles.player_down[0].clone(),
                     ..default()
                 },
-            ))
-            .with_children(|b| {
-                b.spawn((
-                    PlayerHurtFlash,
-                    SpriteBundle {
-                        texture: handles.player_hurt.clone(),
-                        transform: Transform {
-                            translation: vec3(0., 6., -0.001),
-                            ..default()
-                        },
-                        sprite: Sprite {
-                            color: Color::srgba(1., 1., 1., 0.),
-                            ..default()
-                        },
+            ));
+            b.spawn((
+                DeleteOnClear,
+                SpriteBundle {
+                    transform: Transform {
+                        translation: vec3(0., 8., 0.0005),
+                        rotation: Quat::from_rotation_z(cycle_progress * PI * -2.),
                         ..default()
                     },
-                ));
-            });
+                    texture: ctx.handles.cycle_indicator.clone(),
+                    ..default()
+                },
+            ));
+        });
+}
+
+/// A statue firing a fixed pattern while the room is being fought in
+#[derive(Component)]
+pub struct Gargoyle;
+
+pub fn spawn_gargoyle(commands: &mut Commands, entity: &EntityInstance, ctx: &SpawnContext) {
+    let shape = Shape::Fan {
+        count: field_or_warn(entity, "Spread", 1i32).max(1) as u32,
+        spread: field_or_warn(entity, "SpreadAngle", 30.),
+    };
+    let mut pattern = Pattern::new(shape, field_or_warn(entity, "BulletSpeed", 70.));
+    let aim_at_player = field_or_warn(entity, "AimAtPlayer", false);
+    if !aim_at_player {
+        pattern.aim = Aim::Angle(field_or_warn(entity, "Angle", -90.));
+        pattern.spin = field_or_warn(entity, "RotationSpeed", 0.);
     }
+    pattern.volleys = field_or_warn(entity, "Burst", 1i32).max(1) as u32;
+    pattern.volley_delay = field_or_warn(entity, "BurstDelay", 0.15);
+    commands.spawn((
+        Gargoyle,
+        Emitter::new(pattern, field_or_warn(entity, "FireInterval", 3.))
+            .first(field_or_warn(entity, "Phase", 0.)),
+        Telegraph::new(field_or_warn(entity, "Telegraph", 0.5))
+            .flashing()
+            .aiming(aim_at_player),
+        Clearable,
+        Layer(0.),
+        SpriteBundle {
+            transform: Transform::from_translation(ctx.pos.extend(0.) - Vec3::Y * 4.),
+            texture: ctx.handles.gargoyle.clone(),
+            sprite: Sprite {
+                anchor: Anchor::BottomCenter,
+                ..default()
+            },
+            ..default()
+        },
+    ));
 }
 
 pub fn open_door(mut commands: Commands, query: Query<Entity, With<DeleteOnClear>>) {
@@ -328,43 +507,74 @@ pub fn deactivate_gargoyles(
     }
 }
 
-pub fn gargoyles(
-    mut commands: Commands,
-    gargoyles: Query<&Transform, With<Gargoyle>>,
-    handles: Res<Handles>,
-    mut cooldown: Local<f32>,
-    time: Res<Time>,
-) {
-    *cooldown -= time.delta_seconds();
-    if *cooldown < 0. {
-        *cooldown = 3.;
-        for trans in &gargoyles {
-            commands
-                .spawn(())
-                .with_children(|b| {
-                    b.spawn(SpriteBundle {
-                        sprite: Sprite {
-                            color: Color::hsv(57., 0.78, 1.),
-                            ..default()
-                        },
-                        texture: handles.bullet.clone(),
-                        transform: Transform {
-                            translation: vec3(0., 9., 0.),
-                            rotation: Quat::from_rotation_z(0.5 * -PI),
-                            ..default()
-                        },
-                        ..default()
-                    });
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Builds a room from rows given top to bottom: `#` wall, `.` floor, `o` pit
+    pub(crate) fn room(rows: &[&str]) -> Tiles {
+        Tiles {
+            size: ivec2(rows[0].len() as i32, rows.len() as i32),
+            grid: rows
+                .iter()
+                .flat_map(|row| row.chars())
+                .map(|c| match c {
+                    '#' => Tile::Wall,
+                    'o' => Tile::Pit,
+                    _ => Tile::Floor,
                 })
-                .insert((
-                    Layer(0.0),
-                    *trans,
-                    Clearable,
-                    Vel(vec2(0., -70.)),
-                    Bullet { friendly: false },
-                    GlobalTransform::default(),
-                    InheritedVisibility::default(),
-                ));
+                .collect(),
         }
     }
+
+    /// World position of the middle of a cell
+    pub(crate) fn centre(x: i32, y: i32) -> Vec2 {
+        (ivec2(x, y).as_vec2() + 0.5) * CELL_SIZE
+    }
+
+    #[test]
+    fn tiles_from_identifiers() {
+        assert!(Tile::from_identifier("floor") == Some(Tile::Floor));
+        assert!(Tile::from_identifier("oob") == Some(Tile::Wall));
+        assert!(Tile::from_identifier("conveyor_left") == Some(Tile::Conveyor(IVec2::NEG_X)));
+        assert!(Tile::from_identifier("cracked_wall") == Some(Tile::Cracked(3)));
+        assert!(Tile::from_identifier("switch") == Some(Tile::Switch { pressed: false }));
+        assert!(Tile::from_identifier("unknown").is_none());
+    }
+
+    #[test]
+    fn raycast_hits_wall() {
+        let tiles = room(&[".....", ".....", "..#..", ".....", "....."]);
+        let (cell, point) = tiles.raycast(centre(0, 2), centre(4, 2), false).unwrap();
+        assert_eq!(cell, ivec2(2, 2));
+        assert!(point.abs_diff_eq(vec2(2. * CELL_SIZE, 2.5 * CELL_SIZE), 1e-3));
+        assert!(tiles.raycast(centre(0, 0), centre(4, 4), false).is_some());
+        assert!(tiles.raycast(centre(0, 1), centre(4, 1), false).is_none());
+        assert!(tiles.raycast(centre(1, 0), centre(1, 4), false).is_none());
+    }
+
+    #[test]
+    fn raycast_stops_at_room_edge() {
+        let tiles = room(&["...", "...", "..."]);
+        let (cell, point) = tiles
+            .raycast(centre(1, 1), centre(1, 1) + vec2(3. * CELL_SIZE, 0.), false)
+            .unwrap();
+        assert_eq!(cell, ivec2(3, 1));
+        assert!(point.abs_diff_eq(vec2(3. * CELL_SIZE, 1.5 * CELL_SIZE), 1e-3));
+    }
+
+    #[test]
+    fn raycast_blocked_between_diagonal_walls() {
+        let tiles = room(&["...", "#..", ".#."]);
+        assert!(tiles.raycast(centre(0, 0), centre(2, 2), false).is_some());
+        let tiles = room(&["...", "...", "..."]);
+        assert!(tiles.raycast(centre(0, 0), centre(2, 2), false).is_none());
+    }
+
+    #[test]
+    fn sight_crosses_pits() {
+        let tiles = room(&["...", ".o.", "..."]);
+        assert!(tiles.raycast(centre(0, 1), centre(2, 1), false).is_some());
+        assert!(tiles.line_of_sight(centre(0, 1), centre(2, 1)));
+    }
 }
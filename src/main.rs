@@ -1,31 +1,77 @@
 #![allow(clippy::too_many_arguments, clippy::type_complexity)]
+mod affix;
+mod archetype;
 mod aseprite;
+mod behaviour;
+mod boss;
 mod bullet;
+mod camera;
+mod canvas;
 mod collision;
 mod deathscreen;
 mod enemy;
+mod entities;
+mod hazards;
+mod hitstop;
+mod interactive;
 mod ldtk;
 mod level;
+mod map;
 mod music;
+mod particle;
+mod pathfinding;
+mod pattern;
+mod pickup;
+mod pit;
 mod player;
+mod spatial;
+mod telegraph;
+mod tilemap;
+mod transition;
 
+use affix::{explode_on_death, split_on_death, turn_shields};
+use archetype::{EnemyArchetypes, EnemyArchetypesLoader};
 use aseprite::{animations, AnimationData, AsepriteAniLoader, AsepriteImageLoader};
+use behaviour::{
+    chase, contact_damage, deaths, move_enemies, separation, stagger, summoners, wander,
+};
 use bevy::asset::AssetMetaCheck;
+use bevy::math::vec3;
 use bevy::prelude::*;
-use bevy::render::camera::ScalingMode;
+use bevy::render::camera::{CameraUpdateSystem, RenderTarget, ScalingMode};
+use bevy::transform::TransformSystem;
 use bevy::utils::HashMap;
 use bevy_asset_loader::prelude::*;
+use boss::{boss_health_bar, bosses, init_bosses};
 use bullet::move_bullets;
+use camera::{add_trauma, snap_camera, update_camera, CameraController, MainCamera};
+use canvas::{fit_canvas, setup_canvas, snap_to_pixels, Canvas};
 use deathscreen::death_screen;
-use enemy::{floater_a, floater_b, spawn_enemies, spawners, summoner, Enemy, Spawner};
-use ldtk::{LdtkLoader, LdtkProject};
-use level::{deactivate_gargoyles, gargoyles, open_door, spawn_level};
+use enemy::{spawn_enemies, spawners, waves, Enemy, Spawner, Waves};
+use entities::EntityRegistry;
+use hazards::{hazards, reset_hazards};
+use hitstop::{hitstop, start_hitstop, HitstopTimer};
+use interactive::{drop_barriers, press_switches};
+use ldtk::{EnumValue, Fields, LdtkLoader, LdtkProject, Level};
+use level::{
+    deactivate_gargoyles, open_door, spawn_level, Tiles, WORLD_GRID_HEIGHT, WORLD_GRID_WIDTH,
+};
+use map::tower_map;
 use music::{music_volume, play_music, MusicVolume};
+use particle::{emit_particles, forget_particle, particles, ParticleCount};
+use pathfinding::{update_flow_field, FlowField};
+use pattern::emitters;
+use pickup::pickups;
+use pit::{check_pits, falling};
 use player::{
-    player_health, player_hearts_init, player_hurt, player_movement, player_shoot, Player,
-    PlayerEntity,
+    place_hearts, player_health, player_hearts_init, player_hurt, player_movement, player_shoot,
+    Player, PlayerEntity,
 };
 use rand::prelude::*;
+use spatial::{update_enemy_grid, EnemyGrid};
+use telegraph::telegraphs;
+use tilemap::{redraw_tile, spawn_tilemap, Tilemap};
+use transition::{room_transition, RoomTransition, TransitionKind, TransitionOverlay};
 
 fn main() {
     App::new()
@@ -47,39 +93,107 @@ fn main() {
         .register_asset_loader(AsepriteImageLoader)
         .init_asset::<AnimationData>()
         .register_asset_loader(AsepriteAniLoader)
+        .init_asset::<EnemyArchetypes>()
+        .register_asset_loader(EnemyArchetypesLoader)
         .insert_resource(ClearColor(Color::BLACK))
         .init_resource::<Player>()
         .init_resource::<MusicVolume>()
-        .add_systems(OnEnter(LoadState::Loaded), (setup, player_hearts_init))
+        .init_resource::<EntityRegistry>()
+        .init_resource::<FlowField>()
+        .init_resource::<HitstopTimer>()
+        .init_resource::<EnemyGrid>()
+        .init_resource::<ParticleCount>()
+        .add_systems(
+            OnEnter(LoadState::Loaded),
+            (setup_canvas, setup, player_hearts_init).chain(),
+        )
         .add_systems(
             OnEnter(RoomState::Fighting),
-            (spawn_level, spawn_enemies).chain(),
+            (spawn_level, spawn_enemies, reset_hazards).chain(),
         )
         .observe(player_hurt)
+        .observe(add_trauma)
+        .observe(snap_camera)
+        .observe(redraw_tile)
+        .observe(emit_particles)
+        .observe(forget_particle)
+        .observe(start_hitstop)
         .add_systems(
             Update,
             (
-                (player_movement, player_shoot).run_if(not(in_state(RoomState::PlayerDead))),
+                (player_movement, player_shoot, hazards, press_switches)
+                    .run_if(not(in_state(RoomState::PlayerDead))),
                 player_health,
+                update_enemy_grid,
                 move_bullets,
+                update_flow_field,
                 spawners,
-                floater_a,
-                floater_b,
-                summoner,
-                (gargoyles, check_cleared).run_if(in_state(RoomState::Fighting)),
+                (
+                    stagger,
+                    wander,
+                    chase,
+                    move_enemies,
+                    turn_shields,
+                    separation,
+                    contact_damage,
+                    summoners,
+                    split_on_death,
+                    explode_on_death,
+                    deaths,
+                )
+                    .chain(),
+                (init_bosses, bosses, boss_health_bar).chain(),
+                pickups,
+                check_pits,
+                falling,
+                particles,
+                telegraphs,
+                // Flush so the last wave's spawners count before checking for a clear room
+                (emitters, waves, apply_deferred, check_cleared)
+                    .chain()
+                    .run_if(in_state(RoomState::Fighting)),
                 check_exit.run_if(in_state(RoomState::Cleared)),
                 death_screen.run_if(in_state(RoomState::PlayerDead)),
             )
                 .chain()
                 .run_if(in_state(LoadState::Loaded))
-                .run_if(not(in_state(RoomState::Loading))),
+                .run_if(not(in_state(RoomState::Loading)))
+                .run_if(not(in_state(RoomState::Transitioning))),
+        )
+        .add_systems(
+            Update,
+            room_transition.run_if(resource_exists::<RoomTransition>),
         )
         .add_systems(
             OnEnter(RoomState::Cleared),
-            (open_door, deactivate_gargoyles),
+            (open_door, deactivate_gargoyles, drop_barriers),
         )
-        .add_systems(Update, (play_music, music_volume))
+        .add_systems(Update, (play_music, music_volume, hitstop))
+        .add_systems(Update, fit_canvas.run_if(resource_exists::<Canvas>))
+        .add_systems(Update, tower_map.run_if(resource_exists::<Cycle>))
         .add_systems(PostUpdate, (sync_layer, animations, hurt_indicator))
+        .add_systems(
+            PostUpdate,
+            spawn_tilemap
+                .run_if(resource_exists_and_changed::<Tilemap>)
+                .before(sync_layer),
+        )
+        .add_systems(
+            PostUpdate,
+            update_camera
+                .run_if(resource_exists::<Tiles>)
+                .before(TransformSystem::TransformPropagate),
+        )
+        .add_systems(
+            PostUpdate,
+            place_hearts
+                .after(CameraUpdateSystem)
+                .before(TransformSystem::TransformPropagate),
+        )
+        .add_systems(
+            PostUpdate,
+            snap_to_pixels.after(TransformSystem::TransformPropagate),
+        )
         .run();
 }
 
@@ -89,6 +203,7 @@ enum RoomState {
     Loading,
     Fighting,
     Cleared,
+    Transitioning,
     PlayerDead,
 }
 
@@ -104,8 +219,6 @@ struct Handles {
     #[asset(path = "levels.ldtk")]
     ldtk_project: Handle<LdtkProject>,
 
-    #[asset(texture_atlas_layout(tile_size_x = 12, tile_size_y = 12, columns = 12, rows = 12))]
-    layout: Handle<TextureAtlasLayout>,
     #[asset(path = "tiles.aseprite")]
     tiles: Handle<Image>,
 
@@ -134,16 +247,8 @@ struct Handles {
     gargoyle_inactive: Handle<Image>,
     #[asset(path = "summon_ani.aseprite")]
     summon: Handle<AnimationData>,
-    #[asset(path = "floater_a.aseprite")]
-    floater_a: Handle<AnimationData>,
-    #[asset(path = "floater_b.aseprite")]
-    floater_b: Handle<AnimationData>,
-    #[asset(path = "floater_occluded.aseprite")]
-    floater_occluded: Handle<AnimationData>,
-    #[asset(path = "summoner.aseprite")]
-    summoner: Handle<AnimationData>,
-    #[asset(path = "summoner_occluded.aseprite")]
-    summoner_occluded: Handle<AnimationData>,
+    #[asset(path = "enemies.json")]
+    enemies: Handle<EnemyArchetypes>,
     #[asset(path = "door.aseprite")]
     door: Handle<Image>,
     #[asset(path = "grate_circle.aseprite")]
@@ -195,11 +300,29 @@ fn sync_layer(mut query: Query<(&mut Transform, &Layer)>) {
 #[derive(Component, Deref, DerefMut, Copy, Clone, Default, Debug)]
 struct Vel(Vec2);
 
-#[derive(Default, Component)]
-struct Door;
+#[derive(Component)]
+struct Door {
+    destination: Destination,
+}
+
+/// Where a door leads to
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Destination {
+    Next,
+    /// The next room, but one difficulty level higher
+    Harder,
+    Side(RoomKind),
+}
 
-#[derive(Default, Component)]
-struct Gargoyle;
+/// Rooms off the main path of a cycle
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum RoomKind {
+    /// Heals the player, without any enemies
+    Rest,
+    Secret,
+    /// Fought after the last room of each cycle
+    Boss,
+}
 
 fn setup(
     mut commands: Commands,
@@ -207,6 +330,7 @@ fn setup(
     mut windows: Query<&mut Window>,
     mut ldtk: ResMut<Assets<LdtkProject>>,
     mut next_state: ResMut<NextState<RoomState>>,
+    canvas: Res<Canvas>,
 ) {
     let ldtk = ldtk.remove(handles.ldtk_project.id()).unwrap();
     commands.insert_resource(Cycle::new(&ldtk));
@@ -215,11 +339,34 @@ fn setup(
     windows.single_mut().title = "The Girl Who Climbed the Tower".to_owned();
 
     let mut camera = Camera2dBundle {
+        camera: Camera {
+            target: RenderTarget::Image(canvas.image.clone()),
+            ..default()
+        },
         transform: Transform::from_xyz(101., 101., 10.),
         ..default()
     };
-    camera.projection.scaling_mode = ScalingMode::FixedVertical(176.0);
-    commands.spawn(camera);
+    camera.projection.scaling_mode = ScalingMode::WindowSize(1.);
+    commands
+        .spawn((MainCamera, CameraController::default(), camera))
+        .with_children(|b| {
+            b.spawn((
+                TransitionOverlay,
+                SpriteBundle {
+                    texture: handles.black.clone(),
+                    transform: Transform {
+                        translation: Vec3::Z * -6.5,
+                        scale: vec3(400., 400., 1.),
+                        ..default()
+                    },
+                    sprite: Sprite {
+                        color: Color::srgba(0., 0., 0., 0.),
+                        ..default()
+                    },
+                    ..default()
+                },
+            ));
+        });
 
     next_state.set(RoomState::Fighting);
 }
@@ -230,9 +377,10 @@ fn check_cleared(
     query: Query<(), Or<(With<Enemy>, With<Spawner>)>>,
     handles: Res<Handles>,
     cycle: Res<Cycle>,
+    waves: Res<Waves>,
 ) {
-    if query.is_empty() {
-        if (cycle.current_room != 0) | (cycle.cycle != 0) {
+    if query.is_empty() & waves.finished() {
+        if !cycle.is_start() {
             commands.spawn(AudioBundle {
                 source: handles.sfx_clear.clone(),
                 settings: PlaybackSettings {
@@ -250,49 +398,53 @@ fn check_cleared(
 fn check_exit(
     mut commands: Commands,
     player_entity: Query<&Transform, With<PlayerEntity>>,
-    door: Query<&Transform, With<Door>>,
-    clearable: Query<Entity, With<Clearable>>,
-    mut cycle: ResMut<Cycle>,
+    doors: Query<(&Transform, &Door)>,
     mut next_state: ResMut<NextState<RoomState>>,
-    mut player: ResMut<Player>,
 ) {
     // Check for exit
     let player_pos = player_entity.single().translation.xy();
-    let door = door.single().translation.xy();
-    let off = (door - player_pos).abs();
-    if (off.x > 5.) | (off.y > 5.) {
+    for (trans, door) in &doors {
+        let off = (trans.translation.xy() - player_pos).abs();
+        if (off.x > 5.) | (off.y > 5.) {
+            continue;
+        }
+
+        let kind = match door.destination {
+            Destination::Side(RoomKind::Secret) => TransitionKind::Fade,
+            _ => TransitionKind::Slide,
+        };
+        commands.insert_resource(RoomTransition::next_room(kind, door.destination));
+        next_state.set(RoomState::Transitioning);
         return;
     }
+}
 
-    // Clear room
-    for entity in &clearable {
-        commands.entity(entity).despawn_recursive()
+#[derive(Debug)]
+struct Room {
+    id: i32,
+    difficulty: i32,
+    /// Difficulties there are levels of this room for, in increasing order. Side rooms
+    /// may leave gaps between them.
+    difficulties: Vec<i32>,
+}
+
+impl Room {
+    fn max_difficulty(&self) -> i32 {
+        *self.difficulties.last().unwrap()
     }
 
-    // Next room
-    cycle.current_room += 1;
-    if cycle.current_room == cycle.rooms.len() {
-        cycle.current_room = 0;
-        cycle.cycle += 1;
-        let mut rooms = cycle.rooms.iter_mut().collect::<Vec<_>>();
-        rooms.shuffle(&mut thread_rng());
-        for _ in 0..2 {
-            if rooms[0].difficulty < rooms[0].max_difficulty {
-                rooms[0].difficulty += 1;
-            }
-            rooms.remove(0);
+    /// Steps up to the next difficulty there is a level for, if any
+    fn harden(&mut self) {
+        if let Some(&next) = self.difficulties.iter().find(|&&d| d > self.difficulty) {
+            self.difficulty = next;
         }
     }
-    player.spawn_timer = 0.;
-
-    next_state.set(RoomState::Fighting);
 }
 
 #[derive(Debug)]
-struct Room {
-    id: i32,
-    difficulty: i32,
-    max_difficulty: i32,
+struct SideRoom {
+    kind: RoomKind,
+    level_iid: String,
 }
 
 #[derive(Resource)]
@@ -300,31 +452,131 @@ struct Cycle {
     rooms: Vec<Room>,
     current_room: usize,
     cycle: i32,
+    side_rooms: Vec<SideRoom>,
+    /// Index into `side_rooms` if the player took a detour from the main path
+    detour: Option<usize>,
 }
 
 impl Cycle {
     fn new(ldtk: &LdtkProject) -> Self {
         let mut available = HashMap::new();
+        let mut side_rooms = Vec::new();
+        let mut largest: Option<&Level> = None;
         for level in &ldtk.levels {
-            let id = level.world_x / 192;
-            let difficulty = level.world_y / 192;
-            let (min, max) = available.entry(id).or_insert((5, 0));
-            *max = (*max).max(difficulty);
-            *min = (*min).min(difficulty);
+            let kind = level
+                .field_or::<Option<EnumValue>>("RoomKind", None)
+                .unwrap_or_else(|err| {
+                    warn!("{}: {err}", level.identifier);
+                    None
+                });
+            let kind = match kind.as_ref().map(|kind| kind.0.as_str()) {
+                None | Some("Normal") => None,
+                Some("Rest") => Some(RoomKind::Rest),
+                Some("Secret") => Some(RoomKind::Secret),
+                Some("Boss") => Some(RoomKind::Boss),
+                Some(other) => {
+                    warn!("{}: unknown room kind {other}", level.identifier);
+                    None
+                }
+            };
+            if let Some(kind) = kind {
+                side_rooms.push(SideRoom {
+                    kind,
+                    level_iid: level.iid.clone(),
+                });
+                continue;
+            }
+            let area = |level: &Level| level.px_wid * level.px_hei;
+            if largest
+                .filter(|largest| area(largest) >= area(level))
+                .is_none()
+            {
+                largest = Some(level);
+            }
+            let id = level.world_x / WORLD_GRID_WIDTH;
+            let difficulty = level.world_y / WORLD_GRID_HEIGHT;
+            available
+                .entry(id)
+                .or_insert_with(Vec::new)
+                .push(difficulty);
         }
         let mut rooms = Vec::new();
-        for (id, (min, max)) in available {
+        for (id, mut difficulties) in available {
+            difficulties.sort();
             rooms.push(Room {
                 id,
-                difficulty: min,
-                max_difficulty: max,
+                difficulty: difficulties[0],
+                difficulties,
             });
         }
         rooms.shuffle(&mut thread_rng());
+        // Without a dedicated arena, the boss is fought in the largest regular room
+        if !side_rooms.iter().any(|room| room.kind == RoomKind::Boss) {
+            if let Some(level) = largest {
+                side_rooms.push(SideRoom {
+                    kind: RoomKind::Boss,
+                    level_iid: level.iid.clone(),
+                });
+            }
+        }
         Self {
             rooms,
             current_room: 0,
             cycle: 0,
+            side_rooms,
+            detour: None,
+        }
+    }
+
+    /// Whether this is the very first room, which has no enemies
+    fn is_start(&self) -> bool {
+        self.detour.is_none() & (self.current_room == 0) & (self.cycle == 0)
+    }
+
+    fn room_kind(&self) -> Option<RoomKind> {
+        self.detour.map(|i| self.side_rooms[i].kind)
+    }
+
+    /// Picks a random side room of the given kind to take a detour into
+    fn detour_to(&mut self, kind: RoomKind) -> bool {
+        let candidates = (0..self.side_rooms.len())
+            .filter(|&i| self.side_rooms[i].kind == kind)
+            .collect::<Vec<_>>();
+        self.detour = candidates.choose(&mut thread_rng()).copied();
+        self.detour.is_some()
+    }
+
+    fn advance(&mut self, destination: Destination) {
+        if let Destination::Side(kind) = destination {
+            if self.detour_to(kind) {
+                return;
+            }
+            warn!("No {kind:?} room to go to");
+        }
+
+        // The boss waits between the last room of a cycle and the next cycle
+        let leaving_boss = self.room_kind() == Some(RoomKind::Boss);
+        if !leaving_boss & (self.current_room + 1 == self.rooms.len())
+            && self.detour_to(RoomKind::Boss)
+        {
+            return;
+        }
+
+        self.detour = None;
+        self.current_room += 1;
+        if self.current_room == self.rooms.len() {
+            self.current_room = 0;
+            self.cycle += 1;
+            let mut rooms = self.rooms.iter_mut().collect::<Vec<_>>();
+            rooms.shuffle(&mut thread_rng());
+            for _ in 0..2 {
+                rooms[0].harden();
+                rooms.remove(0);
+            }
+        }
+
+        if destination == Destination::Harder {
+            self.rooms[self.current_room].harden();
         }
     }
 }
@@ -333,6 +585,8 @@ impl Cycle {
 pub struct Hurtable {
     pub last_hit: f32,
     pub indicator: Entity,
+    /// Colour of the indicator while not flashing from a hit
+    pub tint: Color,
 }
 
 pub fn hurt_indicator(
@@ -342,7 +596,8 @@ pub fn hurt_indicator(
 ) {
     for mut hurt in &mut query {
         let mut sprite = sprites.get_mut(hurt.indicator).unwrap();
-        sprite.color = Color::srgba(1., 1., 1., (2. - 8. * hurt.last_hit).clamp(0., 1.));
+        let flash = (2. - 8. * hurt.last_hit).clamp(0., 1.);
+        sprite.color = hurt.tint.mix(&Color::WHITE, flash);
         hurt.last_hit += time.delta_seconds();
     }
 }
@@ -0,0 +1,95 @@
+use bevy::{
+    math::{vec2, vec3},
+    prelude::*,
+};
+
+use crate::{camera::MainCamera, Cycle, Handles};
+
+#[derive(Component)]
+pub struct TowerMap;
+
+pub fn tower_map(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    cycle: Res<Cycle>,
+    handles: Res<Handles>,
+    map: Query<Entity, With<TowerMap>>,
+    camera: Query<Entity, With<MainCamera>>,
+) {
+    let open = !map.is_empty();
+    let toggle = keyboard_input.just_pressed(KeyCode::Tab);
+    if !toggle & !(open & cycle.is_changed()) {
+        return;
+    }
+    for entity in &map {
+        commands.entity(entity).despawn_recursive();
+    }
+    if toggle & open {
+        return;
+    }
+
+    let text = |value: String, pos: Vec2, color: Color| Text2dBundle {
+        text: Text::from_section(
+            value,
+            TextStyle {
+                font: handles.font_score.clone(),
+                font_size: 16.,
+                color,
+            },
+        ),
+        transform: Transform::from_translation(pos.extend(0.2)),
+        ..default()
+    };
+
+    commands.entity(camera.single()).with_children(|b| {
+        b.spawn((
+            TowerMap,
+            SpatialBundle::from_transform(Transform::from_xyz(0., 0., -6.2)),
+        ))
+        .with_children(|b| {
+            b.spawn(SpriteBundle {
+                texture: handles.black.clone(),
+                transform: Transform::from_scale(vec3(400., 400., 1.)),
+                sprite: Sprite {
+                    color: Color::srgba(0., 0., 0., 0.85),
+                    ..default()
+                },
+                ..default()
+            });
+            b.spawn(SpriteBundle {
+                texture: handles.ouroboros.clone(),
+                transform: Transform::from_xyz(0., 50., 0.1),
+                ..default()
+            });
+            b.spawn(text(
+                format!("{}", cycle.cycle),
+                vec2(0., 50.),
+                Color::WHITE,
+            ));
+
+            let spacing = (180. / cycle.rooms.len() as f32).min(30.).floor();
+            let left = -spacing * (cycle.rooms.len() - 1) as f32 / 2.;
+            for (i, room) in cycle.rooms.iter().enumerate() {
+                let x = (left + spacing * i as f32).round();
+                let color = if i == cycle.current_room {
+                    Color::hsv(57., 0.78, 1.)
+                } else if i < cycle.current_room {
+                    Color::srgb(0.4, 0.4, 0.4)
+                } else {
+                    Color::WHITE
+                };
+                b.spawn(SpriteBundle {
+                    texture: handles.door.clone(),
+                    transform: Transform::from_xyz(x, -8., 0.1),
+                    sprite: Sprite { color, ..default() },
+                    ..default()
+                });
+                b.spawn(text(
+                    format!("{}/{}", room.difficulty, room.max_difficulty()),
+                    vec2(x, -32.),
+                    color,
+                ));
+            }
+        });
+    });
+}
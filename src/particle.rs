@@ -0,0 +1,189 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::{Clearable, Layer, Vel};
+
+/// Live particles beyond this are not spawned
+#[cfg(target_arch = "wasm32")]
+const MAX_PARTICLES: usize = 256;
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_PARTICLES: usize = 1024;
+
+/// Particles spawned but not yet despawned, including ones still queued in commands
+#[derive(Resource, Default)]
+pub struct ParticleCount(usize);
+
+#[derive(Component)]
+pub struct Particle {
+    age: f32,
+    lifetime: f32,
+    gravity: f32,
+    drag: f32,
+    start: Color,
+    end: Color,
+}
+
+/// A number of particles flying off from one point
+#[derive(Clone)]
+pub struct Burst {
+    pub count: usize,
+    /// Distance from the centre the particles start at
+    pub radius: f32,
+    /// Speed away from the centre, negative to gather towards it
+    pub speed: (f32, f32),
+    /// Pulls particles down the screen, as if falling to the floor
+    pub gravity: f32,
+    /// Fraction of speed lost per second
+    pub drag: f32,
+    pub lifetime: (f32, f32),
+    /// Colour at the start and end of a particle's life
+    pub colors: (Color, Color),
+    /// Drawn as a single pixel without one
+    pub image: Option<Handle<Image>>,
+}
+
+impl Burst {
+    pub fn new(
+        count: usize,
+        speed: (f32, f32),
+        lifetime: (f32, f32),
+        colors: (Color, Color),
+    ) -> Self {
+        Self {
+            count,
+            radius: 0.,
+            speed,
+            gravity: 0.,
+            drag: 0.,
+            lifetime,
+            colors,
+            image: None,
+        }
+    }
+
+    pub fn enemy_death(color: Color) -> Self {
+        Self {
+            drag: 3.,
+            ..Self::new(
+                16,
+                (20., 70.),
+                (0.3, 0.7),
+                (color, Color::hsla(300., 1., 0.85, 0.)),
+            )
+        }
+    }
+
+    pub fn bullet_impact() -> Self {
+        Self {
+            gravity: 120.,
+            ..Self::new(
+                4,
+                (30., 60.),
+                (0.1, 0.25),
+                (Color::WHITE, Color::hsva(57., 0.78, 1., 0.)),
+            )
+        }
+    }
+
+    pub fn summon() -> Self {
+        Self {
+            radius: 12.,
+            ..Self::new(
+                10,
+                (-30., -15.),
+                (0.3, 0.5),
+                (
+                    Color::hsla(300., 1., 0.85, 0.),
+                    Color::hsla(300., 1., 0.85, 1.),
+                ),
+            )
+        }
+    }
+
+    pub fn player_hurt() -> Self {
+        Self {
+            drag: 4.,
+            ..Self::new(
+                12,
+                (40., 80.),
+                (0.2, 0.4),
+                (Color::srgb(1., 0.3, 0.3), Color::srgba(1., 1., 1., 0.)),
+            )
+        }
+    }
+}
+
+fn between((min, max): (f32, f32)) -> f32 {
+    min + (max - min) * thread_rng().gen::<f32>()
+}
+
+#[derive(Event)]
+pub struct EmitParticles {
+    pub pos: Vec2,
+    pub burst: Burst,
+}
+
+pub fn emit_particles(
+    trigger: Trigger<EmitParticles>,
+    mut commands: Commands,
+    mut live: ResMut<ParticleCount>,
+) {
+    let EmitParticles { pos, burst } = trigger.event();
+    let count = burst.count.min(MAX_PARTICLES.saturating_sub(live.0));
+    live.0 += count;
+    for _ in 0..count {
+        let dir = Vec2::from_angle(thread_rng().gen_range(0.0..TAU));
+        let sprite = Sprite {
+            color: burst.colors.0,
+            custom_size: burst.image.is_none().then_some(Vec2::ONE),
+            ..default()
+        };
+        commands.spawn((
+            Particle {
+                age: 0.,
+                lifetime: between(burst.lifetime),
+                gravity: burst.gravity,
+                drag: burst.drag,
+                start: burst.colors.0,
+                end: burst.colors.1,
+            },
+            Vel(dir * between(burst.speed)),
+            Layer(0.5),
+            Clearable,
+            SpriteBundle {
+                sprite,
+                texture: burst.image.clone().unwrap_or_default(),
+                transform: Transform::from_translation((*pos + dir * burst.radius).extend(0.)),
+                ..default()
+            },
+        ));
+    }
+}
+
+/// Also catches particles cleared along with the room
+pub fn forget_particle(_: Trigger<OnRemove, Particle>, mut live: ResMut<ParticleCount>) {
+    live.0 = live.0.saturating_sub(1);
+}
+
+pub fn particles(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Transform, &mut Vel, &mut Sprite, &mut Particle)>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_seconds();
+    for (entity, mut trans, mut vel, mut sprite, mut particle) in &mut query {
+        particle.age += dt;
+        if particle.age >= particle.lifetime {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        vel.0 *= (1. - particle.drag * dt).max(0.);
+        vel.y -= particle.gravity * dt;
+        trans.translation += (vel.0 * dt).extend(0.);
+        sprite.color = particle
+            .start
+            .mix(&particle.end, particle.age / particle.lifetime);
+    }
+}
@@ -0,0 +1,166 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use bevy::{math::ivec2, prelude::*};
+
+use crate::{
+    level::{Tiles, CELL_SIZE},
+    player::PlayerEntity,
+};
+
+const NEIGHBOURS: [IVec2; 8] = [
+    IVec2::X,
+    IVec2::NEG_X,
+    IVec2::Y,
+    IVec2::NEG_Y,
+    IVec2::ONE,
+    IVec2::NEG_ONE,
+    ivec2(1, -1),
+    ivec2(-1, 1),
+];
+
+/// Distances from every cell to the player's cell, for enemies to follow around walls
+#[derive(Resource, Default)]
+pub struct FlowField {
+    target: IVec2,
+    size: IVec2,
+    /// Pits block walking, but not flying
+    walking: Vec<u32>,
+    flying: Vec<u32>,
+}
+
+impl FlowField {
+    fn distance(&self, cell: IVec2, flying: bool) -> u32 {
+        let inside = (0..self.size.x).contains(&cell.x) & (0..self.size.y).contains(&cell.y);
+        if !inside {
+            return u32::MAX;
+        }
+        let distances = if flying { &self.flying } else { &self.walking };
+        distances[(cell.x + cell.y * self.size.x) as usize]
+    }
+
+    /// Direction to move in to get closer to the player. None if the player can't be
+    /// reached or is in the same cell.
+    pub fn direction(&self, tiles: &Tiles, pos: Vec2, flying: bool) -> Option<Vec2> {
+        let cell = Tiles::cell_at(pos);
+        let blocked = |cell: IVec2| !tiles.contains(cell) || tiles[cell].blocks(flying);
+        let mut best = (self.distance(cell, flying), cell);
+        for offset in NEIGHBOURS {
+            // Heading for a diagonal past a wall's corner would just get stuck on it
+            let diagonal = (offset.x != 0) & (offset.y != 0);
+            if diagonal & (blocked(cell + offset * IVec2::X) | blocked(cell + offset * IVec2::Y)) {
+                continue;
+            }
+            let distance = self.distance(cell + offset, flying);
+            if distance < best.0 {
+                best = (distance, cell + offset);
+            }
+        }
+        if best.1 == cell {
+            return None;
+        }
+        let center = (best.1.as_vec2() + 0.5) * CELL_SIZE;
+        Some((center - pos).normalize_or_zero())
+    }
+}
+
+/// Dijkstra from the target over 8 directions, without cutting corners
+fn distances(tiles: &Tiles, target: IVec2, flying: bool) -> Vec<u32> {
+    let passable = |cell: IVec2| tiles.contains(cell) && !tiles[cell].blocks(flying);
+    let index = |cell: IVec2| (cell.x + cell.y * tiles.size.x) as usize;
+    let mut distances = vec![u32::MAX; (tiles.size.x * tiles.size.y) as usize];
+    if !passable(target) {
+        return distances;
+    }
+    distances[index(target)] = 0;
+    let mut queue = BinaryHeap::new();
+    queue.push(Reverse((0, target.x, target.y)));
+    while let Some(Reverse((distance, x, y))) = queue.pop() {
+        let cell = ivec2(x, y);
+        if distance > distances[index(cell)] {
+            continue;
+        }
+        for offset in NEIGHBOURS {
+            let next = cell + offset;
+            let diagonal = (offset.x != 0) & (offset.y != 0);
+            let corner_free =
+                passable(cell + offset * IVec2::X) & passable(cell + offset * IVec2::Y);
+            if !passable(next) | (diagonal & !corner_free) {
+                continue;
+            }
+            let next_distance = distance + if diagonal { 3 } else { 2 };
+            if next_distance < distances[index(next)] {
+                distances[index(next)] = next_distance;
+                queue.push(Reverse((next_distance, next.x, next.y)));
+            }
+        }
+    }
+    distances
+}
+
+pub fn update_flow_field(
+    mut field: ResMut<FlowField>,
+    tiles: Res<Tiles>,
+    player: Query<&Transform, With<PlayerEntity>>,
+) {
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    let target = Tiles::cell_at(player.translation.xy());
+    if (target == field.target) & (tiles.size == field.size) & !tiles.is_changed() {
+        return;
+    }
+    *field = FlowField {
+        target,
+        size: tiles.size,
+        walking: distances(&tiles, target, false),
+        flying: distances(&tiles, target, true),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::tests::{centre, room};
+
+    fn field(tiles: &Tiles, target: IVec2) -> FlowField {
+        FlowField {
+            target,
+            size: tiles.size,
+            walking: distances(tiles, target, false),
+            flying: distances(tiles, target, true),
+        }
+    }
+
+    #[test]
+    fn goes_around_obstacle() {
+        let tiles = room(&[".....", "..#..", "..#.."]);
+        let field = field(&tiles, ivec2(4, 0));
+        assert!(field.distance(ivec2(0, 0), false) < u32::MAX);
+        assert_eq!(field.distance(ivec2(2, 0), false), u32::MAX);
+        // Straight up rather than diagonally past the wall's corner
+        assert_eq!(field.direction(&tiles, centre(1, 1), false), Some(Vec2::Y));
+        assert_eq!(field.direction(&tiles, centre(2, 2), false), Some(Vec2::X));
+        assert!(field
+            .direction(&tiles, centre(3, 1), false)
+            .unwrap()
+            .abs_diff_eq(Vec2::new(1., -1.).normalize(), 1e-5));
+    }
+
+    #[test]
+    fn no_direction_in_target_cell() {
+        let tiles = room(&["...", "...", "..."]);
+        let field = field(&tiles, ivec2(1, 1));
+        assert_eq!(field.direction(&tiles, centre(1, 1), false), None);
+    }
+
+    #[test]
+    fn only_flying_crosses_pits() {
+        let tiles = room(&["...", "ooo", "..."]);
+        let field = field(&tiles, ivec2(1, 0));
+        assert_eq!(field.direction(&tiles, centre(1, 2), false), None);
+        assert_eq!(
+            field.direction(&tiles, centre(1, 2), true),
+            Some(Vec2::NEG_Y)
+        );
+    }
+}
@@ -0,0 +1,322 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use rand::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    bullet::{spawn_bullet, Accel},
+    enemy::Enemy,
+    level::Tiles,
+    pit::Falling,
+    player::PlayerEntity,
+    telegraph::Telegraph,
+    Handles,
+};
+
+/// Where the middle of a volley points
+#[derive(Deserialize, Clone, Copy, Default)]
+pub enum Aim {
+    #[default]
+    Player,
+    /// Degrees counterclockwise from the right
+    Angle(f32),
+}
+
+/// How the bullets of a volley are laid out around the aim
+#[derive(Deserialize, Clone, Copy)]
+pub enum Shape {
+    Single,
+    /// `count` bullets spread evenly over `spread` degrees
+    Fan {
+        count: u32,
+        spread: f32,
+    },
+    /// `count` bullets all around, leaving out `gap` of them where it's aimed
+    Ring {
+        count: u32,
+        #[serde(default)]
+        gap: u32,
+    },
+}
+
+/// A description of an attack: one or more volleys of bullets
+#[derive(Deserialize, Clone)]
+pub struct Pattern {
+    pub shape: Shape,
+    #[serde(default)]
+    pub aim: Aim,
+    /// Degrees per second the aim turns by, for spirals
+    #[serde(default)]
+    pub spin: f32,
+    pub speed: f32,
+    /// Change in speed per second, within `min_speed..=max_speed`
+    #[serde(default)]
+    pub acceleration: f32,
+    #[serde(default)]
+    pub min_speed: f32,
+    #[serde(default = "unbounded")]
+    pub max_speed: f32,
+    #[serde(default = "one")]
+    pub volleys: u32,
+    /// Seconds between the volleys of one attack
+    #[serde(default)]
+    pub volley_delay: f32,
+    /// How far from the emitter bullets appear
+    #[serde(default)]
+    pub offset: f32,
+}
+
+fn unbounded() -> f32 {
+    f32::INFINITY
+}
+
+fn one() -> u32 {
+    1
+}
+
+impl Pattern {
+    pub fn new(shape: Shape, speed: f32) -> Self {
+        Self {
+            shape,
+            aim: Aim::Player,
+            spin: 0.,
+            speed,
+            acceleration: 0.,
+            min_speed: 0.,
+            max_speed: f32::INFINITY,
+            volleys: 1,
+            volley_delay: 0.,
+            offset: 0.,
+        }
+    }
+
+    /// Directions of a volley's bullets in radians, around `aim`
+    pub fn angles(&self, aim: f32) -> Vec<f32> {
+        match self.shape {
+            Shape::Single => vec![aim],
+            Shape::Fan { count, spread } => (0..count)
+                .map(|i| {
+                    if count > 1 {
+                        aim + (i as f32 / (count - 1) as f32 - 0.5) * spread.to_radians()
+                    } else {
+                        aim
+                    }
+                })
+                .collect(),
+            Shape::Ring { count, gap } => {
+                // Centre the gap on the aim
+                let shift = gap.saturating_sub(1) as f32 / 2.;
+                (gap.min(count)..count)
+                    .map(|i| aim + (i as f32 - shift) / count as f32 * TAU)
+                    .collect()
+            }
+        }
+    }
+
+    /// Fires one volley of enemy bullets from `pos`
+    pub fn fire(&self, commands: &mut Commands, handles: &Handles, pos: Vec2, aim: f32) {
+        for angle in self.angles(aim) {
+            let dir = Vec2::from_angle(angle);
+            let mut bullet = spawn_bullet(
+                commands,
+                handles,
+                pos + dir * self.offset,
+                dir * self.speed,
+                false,
+            );
+            if self.acceleration != 0. {
+                bullet.insert(Accel {
+                    rate: self.acceleration,
+                    min: self.min_speed,
+                    max: self.max_speed,
+                });
+            }
+        }
+    }
+}
+
+/// Fires a pattern every `interval` seconds
+#[derive(Component)]
+pub struct Emitter {
+    pub pattern: Pattern,
+    pub interval: f32,
+    /// Chance of each attack actually being fired
+    pub chance: f64,
+    /// Holds fire while the player is out of sight
+    pub needs_sight: bool,
+    timer: f32,
+    /// Whether the next attack goes ahead, decided once its telegraph starts
+    armed: Option<bool>,
+    volleys_left: u32,
+    volley_timer: f32,
+    spin: f32,
+}
+
+impl Emitter {
+    pub fn new(pattern: Pattern, interval: f32) -> Self {
+        Self {
+            pattern,
+            interval,
+            chance: 1.,
+            needs_sight: false,
+            timer: interval,
+            armed: None,
+            volleys_left: 0,
+            volley_timer: 0.,
+            spin: 0.,
+        }
+    }
+
+    /// The first attack comes after `first` seconds
+    pub fn first(self, first: f32) -> Self {
+        Self {
+            timer: first,
+            ..self
+        }
+    }
+
+    pub fn chance(self, chance: f64) -> Self {
+        Self { chance, ..self }
+    }
+
+    pub fn needs_sight(self, needs_sight: bool) -> Self {
+        Self {
+            needs_sight,
+            ..self
+        }
+    }
+
+    /// Progress from 0 to 1 through the last `warning` seconds before an attack that will
+    /// be fired
+    pub fn wind_up(&self, warning: f32) -> Option<f32> {
+        (self.armed == Some(true)).then(|| {
+            if warning > 0. {
+                (1. - self.timer / warning).clamp(0., 1.)
+            } else {
+                1.
+            }
+        })
+    }
+
+    /// Direction in radians the next volley goes in
+    pub fn aim(&self, pos: Vec2, player_pos: Vec2) -> f32 {
+        let aim = match self.pattern.aim {
+            Aim::Player => (player_pos - pos).to_angle(),
+            Aim::Angle(angle) => angle.to_radians(),
+        };
+        aim + self.spin
+    }
+}
+
+pub fn emitters(
+    mut commands: Commands,
+    mut query: Query<
+        (&Transform, &mut Emitter, Option<&Telegraph>, Option<&Enemy>),
+        Without<Falling>,
+    >,
+    player: Query<&Transform, With<PlayerEntity>>,
+    tiles: Res<Tiles>,
+    handles: Res<Handles>,
+    time: Res<Time>,
+) {
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    let player_pos = player.translation.xy();
+    for (trans, mut emitter, telegraph, enemy) in &mut query {
+        if enemy.is_some_and(Enemy::stunned) {
+            continue;
+        }
+        let pos = trans.translation.xy();
+        emitter.spin += emitter.pattern.spin.to_radians() * time.delta_seconds();
+        emitter.timer -= time.delta_seconds();
+        let warning = telegraph.map_or(0., |telegraph| telegraph.warning);
+        if (emitter.timer <= warning) & emitter.armed.is_none() {
+            let sight = !emitter.needs_sight || tiles.line_of_sight(pos, player_pos);
+            emitter.armed = Some(thread_rng().gen_bool(emitter.chance) & sight);
+        }
+        if emitter.timer <= 0. {
+            emitter.timer += emitter.interval;
+            if emitter.armed.take() == Some(true) {
+                emitter.volleys_left = emitter.pattern.volleys;
+                emitter.volley_timer = 0.;
+            }
+        }
+
+        if emitter.volleys_left == 0 {
+            continue;
+        }
+        emitter.volley_timer -= time.delta_seconds();
+        if emitter.volley_timer > 0. {
+            continue;
+        }
+        emitter.volley_timer += emitter.pattern.volley_delay;
+        emitter.volleys_left -= 1;
+
+        let aim = emitter.aim(pos, player_pos);
+        emitter.pattern.fire(&mut commands, &handles, pos, aim);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::*;
+
+    fn assert_angles(angles: Vec<f32>, expected: &[f32]) {
+        assert_eq!(angles.len(), expected.len());
+        for (angle, expected) in angles.iter().zip(expected) {
+            assert!(
+                (angle - expected).abs() < 1e-5,
+                "{angles:?} != {expected:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn single_fires_at_aim() {
+        let pattern = Pattern::new(Shape::Single, 10.);
+        assert_angles(pattern.angles(1.), &[1.]);
+    }
+
+    #[test]
+    fn fan_spreads_around_aim() {
+        let pattern = Pattern::new(
+            Shape::Fan {
+                count: 3,
+                spread: 90.,
+            },
+            10.,
+        );
+        assert_angles(pattern.angles(PI), &[PI * 0.75, PI, PI * 1.25]);
+        let pattern = Pattern::new(
+            Shape::Fan {
+                count: 1,
+                spread: 90.,
+            },
+            10.,
+        );
+        assert_angles(pattern.angles(PI), &[PI]);
+    }
+
+    #[test]
+    fn ring_goes_all_around() {
+        let pattern = Pattern::new(Shape::Ring { count: 4, gap: 0 }, 10.);
+        assert_angles(pattern.angles(0.), &[0., PI / 2., PI, PI * 1.5]);
+    }
+
+    #[test]
+    fn ring_gap_is_centred_on_aim() {
+        let pattern = Pattern::new(Shape::Ring { count: 4, gap: 1 }, 10.);
+        assert_angles(pattern.angles(0.), &[PI / 2., PI, PI * 1.5]);
+        let pattern = Pattern::new(Shape::Ring { count: 8, gap: 3 }, 10.);
+        assert_angles(
+            pattern.angles(0.),
+            &[PI * 0.5, PI * 0.75, PI, PI * 1.25, PI * 1.5],
+        );
+        let pattern = Pattern::new(Shape::Ring { count: 4, gap: 6 }, 10.);
+        assert_angles(pattern.angles(0.), &[]);
+    }
+}
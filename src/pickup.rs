@@ -0,0 +1,70 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    player::{Player, PlayerEntity, MAX_HEALTH, PLAYER_SIZE},
+    Clearable, Handles, Layer,
+};
+
+#[derive(Component, Deserialize, Clone, Copy)]
+pub enum Pickup {
+    /// Restores one heart
+    Heart,
+}
+
+/// Something an enemy may leave behind when it dies
+#[derive(Deserialize, Clone)]
+pub struct DropChance {
+    pub pickup: Pickup,
+    pub chance: f64,
+}
+
+#[derive(Component)]
+pub struct Drops(pub Vec<DropChance>);
+
+pub fn spawn_drops(commands: &mut Commands, drops: &Drops, pos: Vec2, handles: &Handles) {
+    for drop in &drops.0 {
+        if !thread_rng().gen_bool(drop.chance) {
+            continue;
+        }
+        let texture = match drop.pickup {
+            Pickup::Heart => handles.heart.clone(),
+        };
+        commands.spawn((
+            drop.pickup,
+            Clearable,
+            Layer(0.),
+            SpriteBundle {
+                texture,
+                transform: Transform::from_translation(pos.extend(0.)),
+                ..default()
+            },
+        ));
+    }
+}
+
+pub fn pickups(
+    mut commands: Commands,
+    query: Query<(Entity, &Transform, &Pickup)>,
+    player_entity: Query<&Transform, With<PlayerEntity>>,
+    mut player: ResMut<Player>,
+) {
+    let Ok(player_pos) = player_entity.get_single() else {
+        return;
+    };
+    for (entity, trans, pickup) in &query {
+        if trans.translation.xy().distance(player_pos.translation.xy()) > PLAYER_SIZE + 4. {
+            continue;
+        }
+        match pickup {
+            Pickup::Heart => {
+                if player.health >= MAX_HEALTH {
+                    continue;
+                }
+                player.health += 1;
+            }
+        }
+        commands.entity(entity).despawn();
+    }
+}
@@ -0,0 +1,99 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+use crate::{
+    enemy::Enemy,
+    level::{Tile, Tiles},
+    player::{HurtPlayer, Player, PlayerEntity},
+    Vel,
+};
+
+const FALL_TIME: f32 = 0.5;
+
+/// Whether pits can be fallen into in the current room. Otherwise they always block movement.
+#[derive(Resource)]
+pub struct PitFalling(pub bool);
+
+/// Falls into pits when pushed over them
+#[derive(Component)]
+pub struct Grounded;
+
+/// Last floor the player stood on, to respawn at after falling
+#[derive(Component)]
+pub struct SafeGround(pub Vec2);
+
+#[derive(Component, Default)]
+pub struct Falling(f32);
+
+pub fn check_pits(
+    mut commands: Commands,
+    tiles: Res<Tiles>,
+    pit_falling: Res<PitFalling>,
+    player: Res<Player>,
+    mut player_entity: Query<
+        (Entity, &Transform, &mut SafeGround),
+        (With<PlayerEntity>, Without<Falling>),
+    >,
+    grounded: Query<(Entity, &Transform), (With<Grounded>, Without<Falling>)>,
+) {
+    if !pit_falling.0 {
+        return;
+    }
+    if let Ok((entity, trans, mut safe)) = player_entity.get_single_mut() {
+        let pos = trans.translation.xy();
+        match tiles.at(pos) {
+            Some(Tile::Floor) => safe.0 = pos,
+            Some(Tile::Pit) if !player.airborne() => {
+                commands.entity(entity).insert(Falling::default());
+            }
+            _ => {}
+        }
+    }
+    for (entity, trans) in &grounded {
+        if tiles.at(trans.translation.xy()) == Some(Tile::Pit) {
+            commands
+                .entity(entity)
+                .insert(Falling::default())
+                .remove::<Enemy>();
+        }
+    }
+}
+
+pub fn falling(
+    mut commands: Commands,
+    mut query: Query<(
+        Entity,
+        &mut Falling,
+        &mut Transform,
+        Option<&SafeGround>,
+        Option<&mut Vel>,
+    )>,
+    time: Res<Time>,
+) {
+    for (entity, mut falling, mut trans, safe, vel) in &mut query {
+        falling.0 += time.delta_seconds();
+        let progress = (falling.0 / FALL_TIME).min(1.);
+        trans.scale = Vec3::splat(1. - progress);
+        trans.rotation = Quat::from_rotation_z(progress * PI);
+        if progress < 1. {
+            continue;
+        }
+
+        let Some(safe) = safe else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+        trans.translation = safe.0.extend(trans.translation.z);
+        trans.scale = Vec3::ONE;
+        trans.rotation = Quat::IDENTITY;
+        if let Some(mut vel) = vel {
+            vel.0 = Vec2::ZERO;
+        }
+        commands.entity(entity).remove::<Falling>();
+        commands.trigger(HurtPlayer {
+            source: None,
+            unavoidable: true,
+        });
+    }
+}
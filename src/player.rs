@@ -1,11 +1,24 @@
-use bevy::{math::vec3, prelude::*};
+use bevy::{
+    math::{vec2, vec3},
+    prelude::*,
+    sprite::Anchor,
+};
 
 use crate::{
-    bullet::Bullet, collision::grid_collision, level::Tiles, Clearable, Handles, Layer, RoomState,
-    Vel,
+    bullet::spawn_bullet,
+    camera::{MainCamera, Shake},
+    collision::grid_collision,
+    entities::SpawnContext,
+    hazards::{CONVEYOR_SPEED, GOO_SLOWDOWN},
+    ldtk::EntityInstance,
+    level::{Tile, Tiles},
+    particle::{Burst, EmitParticles},
+    pit::{Falling, PitFalling, SafeGround},
+    Clearable, Handles, Layer, RoomState, Vel,
 };
 
 pub const PLAYER_SIZE: f32 = 4.;
+pub const MAX_HEALTH: i32 = 3;
 
 #[derive(Component)]
 pub struct PlayerHurtFlash;
@@ -20,6 +33,16 @@ pub struct Player {
     pub health: i32,
     pub invulnerable: f32,
     pub spawn_timer: f32,
+    dash: f32,
+    dash_cooldown: f32,
+    knockback: f32,
+}
+
+impl Player {
+    /// Dashing or knocked back, which carries the player over pits
+    pub fn airborne(&self) -> bool {
+        (self.dash > 0.) | (self.knockback > 0.)
+    }
 }
 
 impl Default for Player {
@@ -27,9 +50,12 @@ impl Default for Player {
         Self {
             walk_ani: 0.,
             shoot_cooldown: 0.,
-            health: 3,
+            health: MAX_HEALTH,
             invulnerable: 0.,
             spawn_timer: 0.,
+            dash: 0.,
+            dash_cooldown: 0.,
+            knockback: 0.,
         }
     }
 }
@@ -39,17 +65,21 @@ pub fn player_movement(
     time: Res<Time>,
     mut player_entity: Query<
         (&mut Transform, &mut Vel, &mut Sprite, &mut Handle<Image>),
-        With<PlayerEntity>,
+        (With<PlayerEntity>, Without<Falling>),
     >,
     mut player: ResMut<Player>,
     handles: Res<Handles>,
     tiles: Res<Tiles>,
+    pit_falling: Res<PitFalling>,
 ) {
     if time.delta_seconds() <= 0. {
         // TODO: investigate NaN velocity bug, then remove this
         return;
     }
     player.spawn_timer += time.delta_seconds();
+    player.dash -= time.delta_seconds();
+    player.dash_cooldown -= time.delta_seconds();
+    player.knockback -= time.delta_seconds();
     if player.spawn_timer < 0.4 {
         return;
     }
@@ -70,24 +100,46 @@ pub fn player_movement(
         dir += Vec2::Y;
     }
     const PLAYER_SPEED: f32 = 28.;
-    let vel = if dir == Vec2::ZERO {
+    const DASH_SPEED: f32 = 110.;
+    if keyboard_input.just_pressed(KeyCode::Space)
+        & (dir != Vec2::ZERO)
+        & (player.dash_cooldown <= 0.)
+    {
+        player.dash = 0.15;
+        player.dash_cooldown = 0.6;
+        velocity.0 = dir.normalize() * DASH_SPEED;
+    }
+    let ground = tiles.at(pos.translation.xy());
+    let speed = if ground == Some(Tile::Goo) {
+        PLAYER_SPEED * GOO_SLOWDOWN
+    } else {
+        PLAYER_SPEED
+    };
+    let vel = if player.airborne() {
+        velocity.0
+    } else if dir == Vec2::ZERO {
         velocity.0 * (1. - time.delta_seconds() * 15.)
     } else {
-        dir.normalize_or_zero() * PLAYER_SPEED + 0.4 * velocity.0
+        dir.normalize_or_zero() * speed + 0.4 * velocity.0
+    };
+    // Conveyors move the player without adding to their own momentum
+    let push = match ground {
+        Some(Tile::Conveyor(dir)) if !player.airborne() => dir.as_vec2() * CONVEYOR_SPEED,
+        _ => Vec2::ZERO,
     };
-    let attempt_movement = vel * time.delta_seconds();
+    let attempt_movement = (vel + push) * time.delta_seconds();
     let movement = grid_collision(
         &tiles,
         pos.translation.xy(),
         PLAYER_SIZE,
         attempt_movement,
-        false,
+        player.airborne() & pit_falling.0,
     );
     if movement.x.is_nan() | movement.y.is_nan() {
         // TODO: investigate NaN velocity bug, then remove this
         return;
     }
-    velocity.0 = movement / time.delta_seconds();
+    velocity.0 = movement / time.delta_seconds() - push;
     pos.translation += movement.extend(0.);
 
     if dir != Vec2::ZERO {
@@ -114,7 +166,7 @@ pub fn player_shoot(
     mut commands: Commands,
     keyboard_input: Res<ButtonInput<KeyCode>>,
     time: Res<Time>,
-    player_entity: Query<(&Transform, &Vel), With<PlayerEntity>>,
+    player_entity: Query<(&Transform, &Vel), (With<PlayerEntity>, Without<Falling>)>,
     mut player: ResMut<Player>,
     handles: Res<Handles>,
 ) {
@@ -155,46 +207,88 @@ pub fn player_shoot(
             ..default()
         },
     });
+    spawn_bullet(
+        &mut commands,
+        &handles,
+        pos.translation.xy() + dir * 5.,
+        vel,
+        true,
+    );
+}
+
+pub fn spawn_player(commands: &mut Commands, _: &EntityInstance, ctx: &SpawnContext) {
     commands
-        .spawn(())
-        .with_children(|b| {
-            b.spawn(SpriteBundle {
-                texture: handles.bullet.clone(),
-                transform: Transform {
-                    translation: vec3(0., 9., 0.),
-                    rotation: Quat::from_rotation_z(dir.to_angle()),
+        .spawn((
+            PlayerEntity,
+            SafeGround(ctx.pos),
+            Layer(0.0),
+            Clearable,
+            Vel::default(),
+            SpriteBundle {
+                transform: Transform::from_translation(ctx.pos.extend(0.)),
+                sprite: Sprite {
+                    anchor: Anchor::Custom(vec2(0., -0.5 + 3. / 18.)),
                     ..default()
                 },
+                texture: ctx.handles.player_down[0].clone(),
                 ..default()
-            });
-        })
-        .insert((
-            Layer(0.0),
-            Transform::from_translation(pos.translation + dir.extend(0.) * 5.),
-            Clearable,
-            Vel(vel),
-            Bullet { friendly: true },
-            GlobalTransform::default(),
-            InheritedVisibility::default(),
-        ));
+            },
+        ))
+        .with_children(|b| {
+            b.spawn((
+                PlayerHurtFlash,
+                SpriteBundle {
+                    texture: ctx.handles.player_hurt.clone(),
+                    transform: Transform {
+                        translation: vec3(0., 6., -0.001),
+                        ..default()
+                    },
+                    sprite: Sprite {
+                        color: Color::srgba(1., 1., 1., 0.),
+                        ..default()
+                    },
+                    ..default()
+                },
+            ));
+        });
 }
 
 #[derive(Event)]
-pub struct HurtPlayer;
+pub struct HurtPlayer {
+    /// Where the hit came from, to knock the player away from it
+    pub source: Option<Vec2>,
+    /// Hurts even while invulnerable, for falling into pits
+    pub unavoidable: bool,
+}
 
 pub fn player_hurt(
-    _: Trigger<HurtPlayer>,
+    trigger: Trigger<HurtPlayer>,
     mut commands: Commands,
     mut player: ResMut<Player>,
+    mut player_entity: Query<(&Transform, &mut Vel), (With<PlayerEntity>, Without<Falling>)>,
     state: Res<State<RoomState>>,
     mut next: ResMut<NextState<RoomState>>,
     handles: Res<Handles>,
 ) {
-    if (player.invulnerable > 0.) | (*state == RoomState::PlayerDead) {
+    let invulnerable = (player.invulnerable > 0.) & !trigger.event().unavoidable;
+    if invulnerable | (*state == RoomState::PlayerDead) {
         return;
     }
     player.invulnerable = 1.;
     player.health -= 1;
+    commands.trigger(Shake(0.6));
+    if let (Some(source), Ok((trans, mut vel))) =
+        (trigger.event().source, player_entity.get_single_mut())
+    {
+        vel.0 = (trans.translation.xy() - source).normalize_or_zero() * 90.;
+        player.knockback = 0.12;
+    }
+    if let Ok((trans, _)) = player_entity.get_single() {
+        commands.trigger(EmitParticles {
+            pos: trans.translation.xy() + Vec2::Y * 6.,
+            burst: Burst::player_hurt(),
+        });
+    }
     commands.spawn(AudioBundle {
         source: handles.sfx_hurt.clone(),
         settings: PlaybackSettings {
@@ -238,14 +332,31 @@ pub fn player_health(
 #[derive(Component)]
 pub struct HeartUI(i32);
 
-pub fn player_hearts_init(mut commands: Commands) {
-    for i in 1..=3 {
-        commands.spawn((
-            HeartUI(i),
-            SpriteBundle {
-                transform: Transform::from_xyz(-7., 193. - i as f32 * 14., 4.),
-                ..default()
-            },
-        ));
+pub fn player_hearts_init(mut commands: Commands, camera: Query<Entity, With<MainCamera>>) {
+    commands.entity(camera.single()).with_children(|b| {
+        for i in 1..=MAX_HEALTH {
+            b.spawn((
+                HeartUI(i),
+                SpriteBundle {
+                    transform: Transform::from_xyz(-90., 92. - i as f32 * 14., -6.),
+                    ..default()
+                },
+            ));
+        }
+    });
+}
+
+/// Keeps the hearts in the top left corner of the view, which grows without letterboxing
+pub fn place_hearts(
+    camera: Query<&OrthographicProjection, (With<MainCamera>, Changed<OrthographicProjection>)>,
+    mut hearts: Query<(&mut Transform, &HeartUI)>,
+) {
+    let Ok(projection) = camera.get_single() else {
+        return;
+    };
+    let corner = vec2(projection.area.min.x, projection.area.max.y);
+    for (mut trans, heart) in &mut hearts {
+        trans.translation.x = corner.x + 6.;
+        trans.translation.y = corner.y + 4. - heart.0 as f32 * 14.;
     }
 }
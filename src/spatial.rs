@@ -0,0 +1,44 @@
+use bevy::{math::ivec2, prelude::*, utils::HashMap};
+
+use crate::enemy::Enemy;
+
+const GRID_CELL: f32 = 24.;
+
+/// Enemies bucketed by position, rebuilt every tick, so hit and overlap checks only
+/// look at nearby enemies
+#[derive(Resource, Default)]
+pub struct EnemyGrid {
+    cells: HashMap<IVec2, Vec<Entity>>,
+    /// Largest enemy size, to find enemies that reach into a cell from outside it
+    max_size: f32,
+}
+
+impl EnemyGrid {
+    fn cell(pos: Vec2) -> IVec2 {
+        (pos / GRID_CELL).floor().as_ivec2()
+    }
+
+    /// Enemies that may overlap a circle of `radius` around `pos`
+    pub fn near(&self, pos: Vec2, radius: f32) -> impl Iterator<Item = Entity> + '_ {
+        let reach = radius + self.max_size;
+        let min = Self::cell(pos - reach);
+        let max = Self::cell(pos + reach);
+        (min.x..=max.x)
+            .flat_map(move |x| (min.y..=max.y).map(move |y| ivec2(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+    }
+}
+
+pub fn update_enemy_grid(mut grid: ResMut<EnemyGrid>, query: Query<(Entity, &Transform, &Enemy)>) {
+    for cell in grid.cells.values_mut() {
+        cell.clear();
+    }
+    grid.max_size = 0.;
+    for (entity, trans, enemy) in &query {
+        let cell = EnemyGrid::cell(trans.translation.xy());
+        grid.cells.entry(cell).or_default().push(entity);
+        grid.max_size = grid.max_size.max(enemy.size);
+    }
+}
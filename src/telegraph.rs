@@ -0,0 +1,169 @@
+use bevy::{math::vec3, prelude::*, sprite::Anchor};
+use serde::Deserialize;
+
+use crate::{pattern::Emitter, pit::Falling, player::PlayerEntity, Clearable, Layer, RoomState};
+
+const AIM_LINE_LENGTH: f32 = 60.;
+
+/// Warns of an emitter's attacks `warning` seconds before they're fired
+#[derive(Component, Deserialize, Clone)]
+pub struct Telegraph {
+    pub warning: f32,
+    /// Blinks the sprite, faster as the attack gets closer
+    #[serde(default)]
+    pub flash: bool,
+    /// Squashes the sprite as if gathering strength
+    #[serde(default)]
+    pub wind_up: bool,
+    /// Shows a line in the direction the attack is aimed
+    #[serde(default)]
+    pub aim_line: bool,
+    /// Sprite colour and scale to return to after the attack, while winding up
+    #[serde(skip)]
+    base: Option<(Color, Vec3)>,
+    #[serde(skip)]
+    line: Option<Entity>,
+}
+
+impl Telegraph {
+    pub fn new(warning: f32) -> Self {
+        Self {
+            warning,
+            flash: false,
+            wind_up: false,
+            aim_line: false,
+            base: None,
+            line: None,
+        }
+    }
+
+    pub fn flashing(self) -> Self {
+        Self {
+            flash: true,
+            ..self
+        }
+    }
+
+    /// Only meant for attacks aimed at the player, a single line doesn't describe rings
+    /// or spinning patterns
+    pub fn aiming(self, aim_line: bool) -> Self {
+        Self { aim_line, ..self }
+    }
+
+    /// How far along the warning for the emitter's next attack is. Emitters firing more
+    /// often than the warning lasts, like the boss's spiral, would never stop warning, so
+    /// they aren't telegraphed.
+    fn progress(&self, emitter: &Emitter) -> Option<f32> {
+        if emitter.interval <= self.warning {
+            return None;
+        }
+        emitter.wind_up(self.warning)
+    }
+}
+
+/// Points back at the telegraph it belongs to
+#[derive(Component)]
+pub struct AimLine(Entity);
+
+pub fn telegraphs(
+    mut commands: Commands,
+    mut query: Query<
+        (
+            Entity,
+            &mut Telegraph,
+            &Emitter,
+            &mut Sprite,
+            &mut Transform,
+        ),
+        Without<Falling>,
+    >,
+    mut lines: Query<
+        (Entity, &AimLine, &mut Transform, &mut Sprite),
+        (Without<Telegraph>, Without<PlayerEntity>),
+    >,
+    player: Query<&Transform, (With<PlayerEntity>, Without<Telegraph>)>,
+    state: Res<State<RoomState>>,
+) {
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    let player_pos = player.translation.xy();
+    for (entity, mut telegraph, emitter, mut sprite, mut trans) in &mut query {
+        // Emitters stop once the room is cleared, possibly in the middle of a telegraph
+        let progress = telegraph
+            .progress(emitter)
+            .filter(|_| *state == RoomState::Fighting);
+
+        // Leave the sprite to other systems outside of wind-ups
+        match (progress, telegraph.base) {
+            (Some(p), _) => {
+                let (color, scale) = *telegraph.base.get_or_insert((sprite.color, trans.scale));
+                if telegraph.flash {
+                    let on = (p * p * 4.).fract() < 0.5;
+                    sprite.color = if on {
+                        color.mix(&Color::srgb(1., 0.5, 0.5), 0.7)
+                    } else {
+                        color
+                    };
+                }
+                if telegraph.wind_up {
+                    trans.scale = scale * vec3(1. + 0.2 * p, 1. - 0.2 * p, 1.);
+                }
+            }
+            (None, Some((color, scale))) => {
+                if telegraph.flash {
+                    sprite.color = color;
+                }
+                if telegraph.wind_up {
+                    trans.scale = scale;
+                }
+                telegraph.base = None;
+            }
+            (None, None) => {}
+        }
+
+        if !telegraph.aim_line {
+            continue;
+        }
+        match (progress, telegraph.line) {
+            (Some(_), None) => {
+                let line = commands
+                    .spawn((
+                        AimLine(entity),
+                        Clearable,
+                        Layer(-0.7),
+                        SpriteBundle {
+                            sprite: Sprite {
+                                anchor: Anchor::CenterLeft,
+                                custom_size: Some(Vec2::new(AIM_LINE_LENGTH, 1.)),
+                                color: Color::NONE,
+                                ..default()
+                            },
+                            transform: *trans,
+                            ..default()
+                        },
+                    ))
+                    .id();
+                telegraph.line = Some(line);
+            }
+            (None, Some(line)) => {
+                commands.entity(line).despawn();
+                telegraph.line = None;
+            }
+            _ => {}
+        }
+    }
+
+    for (entity, line, mut trans, mut sprite) in &mut lines {
+        let Ok((_, telegraph, emitter, _, owner)) = query.get(line.0) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        let pos = owner.translation.xy();
+        let p = telegraph.progress(emitter).unwrap_or(0.);
+        trans.translation = pos.extend(trans.translation.z);
+        trans.rotation = Quat::from_rotation_z(emitter.aim(pos, player_pos));
+        trans.scale = Vec3::ONE;
+        sprite.color = Color::srgba(1., 0.3, 0.3, 0.15 + 0.5 * p);
+    }
+}
@@ -0,0 +1,444 @@
+use bevy::{
+    math::ivec2,
+    prelude::*,
+    render::{
+        mesh::{Indices, PrimitiveTopology},
+        render_asset::RenderAssetUsages,
+    },
+    sprite::{Anchor, MaterialMesh2dBundle, Mesh2dHandle},
+    utils::HashMap,
+};
+
+use crate::{
+    level::{Tile, TileChanged, Tiles, CELL_SIZE},
+    Clearable, Layer,
+};
+
+/// Sprites drawn over tiles that have no tiles of their own in the tileset, by cell
+#[derive(Resource, Default)]
+pub struct TileOverlays(HashMap<IVec2, Entity>);
+
+/// A rectangle of an overlay, in pixels from the bottom left corner of its cell
+struct Part {
+    min: IVec2,
+    size: IVec2,
+    color: Color,
+}
+
+fn part(x: i32, y: i32, w: i32, h: i32, color: Color) -> Part {
+    Part {
+        min: ivec2(x, y),
+        size: ivec2(w, h),
+        color,
+    }
+}
+
+/// Turns a part drawn for a rightwards conveyor to face `dir`
+fn rotate(part: Part, dir: IVec2) -> Part {
+    let cell = CELL_SIZE as i32;
+    let Part { min, size, color } = part;
+    let (min, size) = match (dir.x, dir.y) {
+        (-1, _) => (ivec2(cell - min.x - size.x, min.y), size),
+        (_, 1) => (ivec2(cell - min.y - size.y, min.x), size.yx()),
+        (_, -1) => (ivec2(min.y, cell - min.x - size.x), size.yx()),
+        _ => (min, size),
+    };
+    Part { min, size, color }
+}
+
+/// What to draw for a tile, bottommost first, and the layer to draw it on
+fn overlay(tile: Tile) -> Option<(f32, Vec<Part>)> {
+    let dark = Color::srgb_u8(0x26, 0x2b, 0x44);
+    let steel = Color::srgb_u8(0x5a, 0x69, 0x88);
+    let light = Color::srgb_u8(0xc0, 0xcb, 0xdc);
+    let floor = -0.9;
+    Some(match tile {
+        Tile::Spikes => {
+            let mut parts = vec![part(0, 0, 12, 12, steel)];
+            for (x, y) in [(2, 2), (8, 2), (2, 8), (8, 8)] {
+                parts.push(part(x, y - 1, 2, 2, dark));
+                parts.push(part(x, y, 2, 2, light));
+                parts.push(part(x, y + 2, 1, 1, Color::WHITE));
+            }
+            (floor, parts)
+        }
+        Tile::Goo => (
+            floor,
+            vec![
+                part(0, 0, 12, 12, Color::srgb_u8(0x26, 0x5c, 0x42)),
+                part(1, 1, 10, 10, Color::srgb_u8(0x3e, 0x89, 0x48)),
+                part(3, 6, 2, 2, Color::srgb_u8(0x63, 0xc7, 0x4d)),
+                part(7, 3, 2, 2, Color::srgb_u8(0x63, 0xc7, 0x4d)),
+            ],
+        ),
+        Tile::Conveyor(dir) => {
+            let arrow = Color::srgb_u8(0xfe, 0xae, 0x34);
+            let parts = [
+                part(0, 0, 12, 12, dark),
+                part(0, 0, 12, 1, steel),
+                part(0, 11, 12, 1, steel),
+                part(2, 5, 5, 2, arrow),
+                part(7, 3, 2, 6, arrow),
+                part(9, 4, 1, 4, arrow),
+                part(10, 5, 1, 2, arrow),
+            ];
+            (floor, parts.into_iter().map(|p| rotate(p, dir)).collect())
+        }
+        Tile::Crumbling => {
+            let crack = Color::srgb_u8(0x73, 0x3e, 0x39);
+            (
+                floor,
+                vec![
+                    part(0, 0, 12, 12, Color::srgb_u8(0xb8, 0x6f, 0x50)),
+                    part(2, 7, 5, 1, crack),
+                    part(6, 4, 1, 4, crack),
+                    part(8, 2, 3, 1, crack),
+                ],
+            )
+        }
+        Tile::Cracked(hits) => {
+            let crack = Color::srgb_u8(0x18, 0x14, 0x25);
+            let mut parts = vec![
+                part(0, 0, 12, 12, steel),
+                part(0, 0, 12, 3, dark),
+                part(0, 6, 12, 1, dark),
+                part(5, 3, 1, 3, dark),
+                part(3, 7, 1, 5, dark),
+                part(8, 7, 1, 5, dark),
+            ];
+            // Every hit taken widens the crack
+            let cracks = [
+                part(6, 8, 1, 3, crack),
+                part(5, 4, 2, 2, crack),
+                part(2, 9, 3, 1, crack),
+                part(9, 4, 2, 1, crack),
+                part(7, 10, 3, 1, crack),
+            ];
+            let taken = 3 - hits.min(3) as usize;
+            parts.extend(cracks.into_iter().take(1 + 2 * taken));
+            (0., parts)
+        }
+        Tile::Switch { pressed } => {
+            let (button, height) = match pressed {
+                false => (Color::srgb_u8(0x2c, 0xe8, 0xf5), 3),
+                true => (Color::srgb_u8(0x12, 0x4e, 0x89), 1),
+            };
+            (
+                floor,
+                vec![
+                    part(1, 1, 10, 10, steel),
+                    part(2, 2, 8, 8, dark),
+                    part(4, 4, 4, 4 + height - 1, button),
+                ],
+            )
+        }
+        Tile::Gate => {
+            let mut parts = vec![part(0, 0, 12, 12, dark), part(0, 9, 12, 2, steel)];
+            for x in [1, 5, 9] {
+                parts.push(part(x, 0, 2, 12, steel));
+                parts.push(part(x, 0, 1, 12, light));
+            }
+            (0., parts)
+        }
+        Tile::Barrier => {
+            let red = Color::srgb_u8(0xe4, 0x3b, 0x44);
+            (
+                0.,
+                vec![
+                    part(0, 0, 2, 12, steel),
+                    part(10, 0, 2, 12, steel),
+                    part(0, 3, 12, 2, red),
+                    part(0, 8, 12, 2, red),
+                    part(0, 5, 12, 1, Color::srgb_u8(0x9e, 0x28, 0x35)),
+                    part(0, 10, 12, 1, Color::srgb_u8(0x9e, 0x28, 0x35)),
+                ],
+            )
+        }
+        _ => return None,
+    })
+}
+
+fn spawn_overlay(commands: &mut Commands, cell: IVec2, tile: Tile) -> Option<Entity> {
+    let (layer, parts) = overlay(tile)?;
+    let pos = cell.as_vec2() * CELL_SIZE;
+    let entity = commands
+        .spawn((
+            Clearable,
+            Layer(layer),
+            SpatialBundle::from_transform(Transform::from_translation(pos.extend(0.))),
+        ))
+        .with_children(|b| {
+            for (i, part) in parts.into_iter().enumerate() {
+                b.spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: part.color,
+                        custom_size: Some(part.size.as_vec2()),
+                        anchor: Anchor::BottomLeft,
+                        ..default()
+                    },
+                    transform: Transform::from_translation(
+                        part.min.as_vec2().extend(i as f32 * 0.0001),
+                    ),
+                    ..default()
+                });
+            }
+        })
+        .id();
+    Some(entity)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ZLayer {
+    Subfloor,
+    Floor,
+    Wall,
+    Top,
+}
+
+impl ZLayer {
+    fn base_z(self) -> f32 {
+        match self {
+            ZLayer::Subfloor => -2.,
+            ZLayer::Floor => -1.,
+            ZLayer::Wall => 0.,
+            ZLayer::Top => 1.,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct PlacedTile {
+    /// Bottom left corner in world space
+    pub pos: Vec2,
+    /// Top left corner in the tileset, in pixels
+    pub src: IVec2,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    pub alpha: f32,
+    pub z_layer: ZLayer,
+}
+
+#[derive(Component)]
+pub struct TilemapChunk;
+
+/// Tiles drawn in the current room, grouped by the cell they cover
+#[derive(Resource)]
+pub struct Tilemap {
+    material: Handle<ColorMaterial>,
+    tileset_size: Vec2,
+    cells: HashMap<IVec2, Vec<PlacedTile>>,
+}
+
+impl Tilemap {
+    /// Tiles are given in drawing order (bottommost first)
+    pub fn new(
+        material: Handle<ColorMaterial>,
+        tileset_size: Vec2,
+        tiles: Vec<PlacedTile>,
+    ) -> Self {
+        let mut cells = HashMap::<_, Vec<_>>::new();
+        for tile in tiles {
+            cells
+                .entry(Tiles::cell_at(tile.pos + CELL_SIZE / 2.))
+                .or_default()
+                .push(tile);
+        }
+        Self {
+            material,
+            tileset_size,
+            cells,
+        }
+    }
+}
+
+/// Redraws a changed cell with its overlay if it has one, and otherwise like the most
+/// similar cell of the same tile kind
+pub fn redraw_tile(
+    trigger: Trigger<TileChanged>,
+    mut commands: Commands,
+    mut tilemap: ResMut<Tilemap>,
+    mut overlays: ResMut<TileOverlays>,
+    tiles: Res<Tiles>,
+) {
+    let pos = trigger.event().0;
+    let tile = tiles[pos];
+    if let Some(old) = overlays.0.remove(&pos) {
+        commands.entity(old).despawn_recursive();
+    }
+    if let Some(overlay) = spawn_overlay(&mut commands, pos, tile) {
+        overlays.0.insert(pos, overlay);
+        tilemap.cells.remove(&pos);
+        return;
+    }
+    let same_neighbours = |cell: IVec2| {
+        let mut count = 0;
+        for x in -1..=1 {
+            for y in -1..=1 {
+                let neighbour = cell + ivec2(x, y);
+                if tiles.contains(neighbour) && (tiles[neighbour] == tile) {
+                    count += 1;
+                }
+            }
+        }
+        count
+    };
+    let mut reference = None;
+    let mut best = -1;
+    for x in 0..tiles.size.x {
+        for y in 0..tiles.size.y {
+            let cell = ivec2(x, y);
+            if (cell != pos) & (tiles[cell] == tile) & tilemap.cells.contains_key(&cell) {
+                let count = same_neighbours(cell);
+                if count > best {
+                    best = count;
+                    reference = Some(cell);
+                }
+            }
+        }
+    }
+
+    match reference {
+        Some(reference) => {
+            let offset = (pos - reference).as_vec2() * CELL_SIZE;
+            let stack = tilemap.cells[&reference]
+                .iter()
+                .map(|tile| PlacedTile {
+                    pos: tile.pos + offset,
+                    ..tile.clone()
+                })
+                .collect();
+            tilemap.cells.insert(pos, stack);
+        }
+        // Without anything to copy, pits are simply left dark
+        None if tile == Tile::Pit => {
+            tilemap.cells.remove(&pos);
+        }
+        None => {}
+    }
+}
+
+/// Builds one mesh per z-layer, rebuilding everything whenever the tilemap changes.
+/// Walls are split into rows so that they can be y-sorted with sprites.
+pub fn spawn_tilemap(
+    mut commands: Commands,
+    tilemap: Res<Tilemap>,
+    chunks: Query<Entity, With<TilemapChunk>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    for entity in &chunks {
+        commands.entity(entity).despawn();
+    }
+
+    let mut chunks = HashMap::<_, Vec<&PlacedTile>>::new();
+    for tile in tilemap.cells.values().flatten() {
+        let row = (tile.z_layer == ZLayer::Wall).then_some(tile.pos.y as i32);
+        chunks.entry((tile.z_layer, row)).or_default().push(tile);
+    }
+
+    for ((z_layer, row), tiles) in chunks {
+        let origin = Vec2::Y * row.unwrap_or(0) as f32;
+        let mut positions = Vec::new();
+        let mut uvs = Vec::new();
+        let mut colors = Vec::new();
+        let mut indices = Vec::new();
+        for tile in tiles {
+            let i = positions.len() as u32;
+            let min = tile.pos - origin;
+            let max = min + CELL_SIZE;
+            positions.extend([
+                [min.x, min.y, 0.],
+                [max.x, min.y, 0.],
+                [max.x, max.y, 0.],
+                [min.x, max.y, 0.],
+            ]);
+            let mut uv_min = tile.src.as_vec2() / tilemap.tileset_size;
+            let mut uv_max = (tile.src.as_vec2() + CELL_SIZE) / tilemap.tileset_size;
+            if tile.flip_x {
+                std::mem::swap(&mut uv_min.x, &mut uv_max.x);
+            }
+            if tile.flip_y {
+                std::mem::swap(&mut uv_min.y, &mut uv_max.y);
+            }
+            colors.extend([[1., 1., 1., tile.alpha]; 4]);
+            uvs.extend([
+                [uv_min.x, uv_max.y],
+                [uv_max.x, uv_max.y],
+                [uv_max.x, uv_min.y],
+                [uv_min.x, uv_min.y],
+            ]);
+            indices.extend([i, i + 1, i + 2, i, i + 2, i + 3]);
+        }
+        let mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::RENDER_WORLD,
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+        .with_inserted_indices(Indices::U32(indices));
+
+        commands.spawn((
+            TilemapChunk,
+            Clearable,
+            Layer(z_layer.base_z()),
+            MaterialMesh2dBundle {
+                mesh: Mesh2dHandle(meshes.add(mesh)),
+                material: tilemap.material.clone(),
+                transform: Transform::from_translation(origin.extend(0.)),
+                ..default()
+            },
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlays_stay_in_their_cell() {
+        let cell = CELL_SIZE as i32;
+        let hazards = [
+            Tile::Spikes,
+            Tile::Goo,
+            Tile::Crumbling,
+            Tile::Conveyor(IVec2::X),
+            Tile::Conveyor(IVec2::NEG_X),
+            Tile::Conveyor(IVec2::Y),
+            Tile::Conveyor(IVec2::NEG_Y),
+            Tile::Cracked(3),
+            Tile::Cracked(2),
+            Tile::Cracked(1),
+            Tile::Switch { pressed: false },
+            Tile::Switch { pressed: true },
+            Tile::Gate,
+            Tile::Barrier,
+        ];
+        for tile in hazards {
+            let (_, parts) = overlay(tile).unwrap();
+            for part in parts {
+                assert!(part.min.cmpge(IVec2::ZERO).all());
+                assert!((part.min + part.size).cmple(IVec2::splat(cell)).all());
+            }
+        }
+        assert!(overlay(Tile::Floor).is_none());
+    }
+
+    #[test]
+    fn cracks_grow_with_hits() {
+        let parts = |hits| overlay(Tile::Cracked(hits)).unwrap().1.len();
+        assert!(parts(3) < parts(2));
+        assert!(parts(2) < parts(1));
+    }
+
+    #[test]
+    fn conveyor_arrows_point_along_the_belt() {
+        // The tip of the arrow is its smallest part, nearest the edge it points to
+        for dir in [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y] {
+            let (_, parts) = overlay(Tile::Conveyor(dir)).unwrap();
+            let tip = parts.last().unwrap();
+            let centre = tip.min.as_vec2() + tip.size.as_vec2() / 2. - CELL_SIZE / 2.;
+            assert!(centre.dot(dir.as_vec2()) > 3., "{dir}");
+        }
+    }
+}
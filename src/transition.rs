@@ -0,0 +1,96 @@
+use bevy::prelude::*;
+
+use crate::{
+    player::{Player, MAX_HEALTH},
+    Clearable, Cycle, Destination, RoomKind, RoomState,
+};
+
+// Duration of each of the two halves (leaving the old room, entering the new one)
+const HALF: f32 = 0.35;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TransitionKind {
+    Fade,
+    Slide,
+}
+
+#[derive(Resource)]
+pub struct RoomTransition {
+    pub kind: TransitionKind,
+    timer: f32,
+    /// Room change still to happen
+    pending: Option<Destination>,
+}
+
+impl RoomTransition {
+    /// Leaves the current room and enters the next one
+    pub fn next_room(kind: TransitionKind, destination: Destination) -> Self {
+        Self {
+            kind,
+            timer: 0.,
+            pending: Some(destination),
+        }
+    }
+
+    /// Only fades into the current room, e.g. after respawning
+    pub fn fade_in() -> Self {
+        Self {
+            kind: TransitionKind::Fade,
+            timer: HALF,
+            pending: None,
+        }
+    }
+
+    /// 0 when fully in a room, 1 at the switch between rooms
+    fn progress(&self) -> f32 {
+        1. - (self.timer / HALF - 1.).abs().min(1.)
+    }
+
+    pub fn slide_offset(&self, view: Vec2) -> Vec2 {
+        if self.kind != TransitionKind::Slide {
+            return Vec2::ZERO;
+        }
+        // Pan upwards out of the old room, then in from below into the new one
+        let sign = if self.timer < HALF { 1. } else { -1. };
+        Vec2::Y * sign * view.y * self.progress().powi(2)
+    }
+}
+
+#[derive(Component)]
+pub struct TransitionOverlay;
+
+pub fn room_transition(
+    mut commands: Commands,
+    mut transition: ResMut<RoomTransition>,
+    mut overlay: Query<&mut Sprite, With<TransitionOverlay>>,
+    clearable: Query<Entity, With<Clearable>>,
+    mut cycle: ResMut<Cycle>,
+    mut player: ResMut<Player>,
+    mut next_state: ResMut<NextState<RoomState>>,
+    time: Res<Time>,
+) {
+    transition.timer += time.delta_seconds();
+
+    if let (Some(destination), true) = (transition.pending, transition.timer >= HALF) {
+        transition.pending = None;
+        for entity in &clearable {
+            commands.entity(entity).despawn_recursive()
+        }
+        cycle.advance(destination);
+        if cycle.room_kind() == Some(RoomKind::Rest) {
+            player.health = (player.health + 1).min(MAX_HEALTH);
+        }
+        player.spawn_timer = 0.;
+        next_state.set(RoomState::Fighting);
+    }
+
+    let alpha = match transition.kind {
+        TransitionKind::Fade => transition.progress(),
+        TransitionKind::Slide => 0.,
+    };
+    overlay.single_mut().color = Color::srgba(0., 0., 0., alpha);
+
+    if transition.timer >= 2. * HALF {
+        commands.remove_resource::<RoomTransition>();
+    }
+}